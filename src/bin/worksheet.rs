@@ -0,0 +1,38 @@
+// Command-line helpers for the worksheet.
+//
+//     worksheet explain Q0001    Explain a QueryError code.
+//     worksheet palette [--256 | --16] [color...]
+//                                Show colors as terminal swatches.
+
+use std::process::ExitCode;
+
+use dlcm_rust_workshop::enums_match::ansi::{palette_command, ColorDepth};
+use dlcm_rust_workshop::errors::catalog::explain_command;
+
+const USAGE: &str =
+    "usage: worksheet explain <code>\n       worksheet palette [--256 | --16] [color...]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["explain", code] => explain_command(code),
+        ["palette", rest @ ..] => {
+            let colorterm = std::env::var("COLORTERM").ok();
+            let term = std::env::var("TERM").ok();
+            let depth = ColorDepth::detect(colorterm.as_deref(), term.as_deref());
+            palette_command(rest, depth)
+        }
+        _ => Err(USAGE.to_owned()),
+    };
+    match result {
+        Ok(output) => {
+            print!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,7 +1,7 @@
-/// Rust's ownership and lifetime rules make closures safer, but require you
-/// to think about the values that the closure closes over.  Since they may
-/// be mutated or consumed, it may be impossible to call the closure more
-/// than once, or to have the closure be used by multiple owners.
+//! Rust's ownership and lifetime rules make closures safer, but require you
+//! to think about the values that the closure closes over.  Since they may
+//! be mutated or consumed, it may be impossible to call the closure more
+//! than once, or to have the closure be used by multiple owners.
 
 // Full closure signature:
 // |p1: type1, p2: type2| -> ret_type { body }
@@ -12,8 +12,229 @@
 // |p1       , p2       |               expr
 // Rust's inference is pretty good, but sometimes you'll need to put in type information.
 
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+// Closures earn their keep as callbacks.  An event bus keeps a list of them,
+// boxed since every closure has its own type, and calls each one per event.
+// They're `FnMut`, so a handler can keep state between events; one-shot
+// handlers are `FnOnce`, and are dropped once called.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+enum Handler<E> {
+    Every(Box<dyn FnMut(&E)>),
+    Once(Box<dyn FnOnce(&E)>),
+}
+
+pub struct EventBus<E> {
+    next_id: u64,
+    // In subscription order, which is the order they're called in.
+    handlers: Vec<(SubscriptionId, Handler<E>)>,
+}
+
+impl<E> EventBus<E> {
+    pub fn new() -> Self {
+        EventBus {
+            next_id: 0,
+            handlers: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, handler: Handler<E>) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.handlers.push((id, handler));
+        id
+    }
+
+    /// Call `handler` with every event published from now on.
+    pub fn subscribe(&mut self, handler: impl FnMut(&E) + 'static) -> SubscriptionId {
+        self.add(Handler::Every(Box::new(handler)))
+    }
+
+    /// Call `handler` with the next event only.
+    pub fn subscribe_once(&mut self, handler: impl FnOnce(&E) + 'static) -> SubscriptionId {
+        self.add(Handler::Once(Box::new(handler)))
+    }
+
+    /// Returns false if `id` wasn't subscribed: it was already removed, or
+    /// it was a one-shot handler that has run.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.handlers.len();
+        self.handlers.retain(|(other, _)| *other != id);
+        self.handlers.len() != before
+    }
+
+    /// If a handler panics, it's dropped, but every other handler stays
+    /// subscribed.
+    pub fn publish(&mut self, event: &E) {
+        // Calling a FnOnce consumes it, so take every handler out, and only
+        // put back the ones that can be called again.
+        let handlers = std::mem::take(&mut self.handlers);
+        let mut pending = Pending {
+            kept: &mut self.handlers,
+            rest: handlers.into_iter(),
+        };
+        for (id, handler) in pending.rest.by_ref() {
+            match handler {
+                Handler::Every(mut f) => {
+                    f(event);
+                    pending.kept.push((id, Handler::Every(f)));
+                }
+                Handler::Once(f) => f(event),
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.handlers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+}
+
+// Puts back the handlers `publish` hasn't reached yet when it's dropped,
+// which also happens when a handler panics and unwinds through `publish`.
+struct Pending<'a, E> {
+    kept: &'a mut Vec<(SubscriptionId, Handler<E>)>,
+    rest: std::vec::IntoIter<(SubscriptionId, Handler<E>)>,
+}
+
+impl<E> Drop for Pending<'_, E> {
+    fn drop(&mut self) {
+        self.kept.extend(self.rest.by_ref());
+    }
+}
+
+impl<E> Default for EventBus<E> {
+    fn default() -> Self {
+        EventBus::new()
+    }
+}
+
+// Memoizing only works for `Fn`: it's called any number of times, and the
+// same argument must always give the same result.  So `F` only needs `&self`,
+// and the cache needs a RefCell to be filled in through `&self` too.
+
+pub struct Memoize<F, A, R> {
+    f: F,
+    cache: RefCell<HashMap<A, R>>,
+}
+
+impl<F, A, R> Memoize<F, A, R>
+where
+    F: Fn(A) -> R,
+    A: Eq + Hash + Clone,
+    R: Clone,
+{
+    pub fn new(f: F) -> Self {
+        Memoize {
+            f,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, arg: A) -> R {
+        if let Some(result) = self.cache.borrow().get(&arg) {
+            return result.clone();
+        }
+        let result = (self.f)(arg.clone());
+        self.cache.borrow_mut().insert(arg, result.clone());
+        result
+    }
+
+    /// How many results are cached.
+    pub fn len(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.borrow().is_empty()
+    }
+}
+
+// A closure can't call itself, since it has no name.  So a recursive one
+// takes a `recurse` argument to call instead, which goes through the cache.
+
+pub struct MemoizeRecursive<F, A, R> {
+    f: F,
+    cache: RefCell<HashMap<A, R>>,
+}
+
+impl<F, A, R> MemoizeRecursive<F, A, R>
+where
+    F: Fn(&dyn Fn(A) -> R, A) -> R,
+    A: Eq + Hash + Clone,
+    R: Clone,
+{
+    pub fn new(f: F) -> Self {
+        MemoizeRecursive {
+            f,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, arg: A) -> R {
+        if let Some(result) = self.cache.borrow().get(&arg) {
+            return result.clone();
+        }
+        // The cache isn't borrowed during the call, since `f` will use it.
+        let result = (self.f)(&|arg| self.get(arg), arg.clone());
+        self.cache.borrow_mut().insert(arg, result.clone());
+        result
+    }
+}
+
+// `Lazy` is the `FnOnce` counterpart: it runs `init` at most once, the first
+// time the value is needed.  Running it uses it up, so it's kept in an
+// Option, to be taken out.  (std has this as `std::cell::LazyCell`.)
+
+pub struct Lazy<T, F = fn() -> T> {
+    value: OnceCell<T>,
+    init: Cell<Option<F>>,
+}
+
+impl<T, F: FnOnce() -> T> Lazy<T, F> {
+    pub const fn new(init: F) -> Self {
+        Lazy {
+            value: OnceCell::new(),
+            init: Cell::new(Some(init)),
+        }
+    }
+
+    /// Panics if `init` panicked on an earlier call, or if `init` calls
+    /// `get` on this same `Lazy`: either way there's no `init` left to run.
+    pub fn get(&self) -> &T {
+        self.value.get_or_init(|| match self.init.take() {
+            Some(init) => init(),
+            None => panic!("Lazy's init panicked, or called get() on itself"),
+        })
+    }
+
+    /// Whether `init` has run yet.
+    pub fn is_ready(&self) -> bool {
+        self.value.get().is_some()
+    }
+}
+
+impl<T, F: FnOnce() -> T> std::ops::Deref for Lazy<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::rc::Rc;
+
     #[test]
     fn test_closures_fn() {
         // It's easiest if the closure doesn't capture anything
@@ -71,4 +292,230 @@ mod tests {
             "#
         );
     }
+
+    #[test]
+    fn test_closures_event_bus_fn_mut() {
+        // The practical side of `exercise_closures_fn_mut`.  The bus needs
+        // 'static handlers, so state we want to look at afterwards is shared
+        // with Rc<RefCell<..>>.
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new();
+
+        let log = Rc::clone(&seen);
+        // `count` moves into the closure, and lives on between calls.
+        let mut count = 0;
+        bus.subscribe(move |event: &String| {
+            count += 1;
+            log.borrow_mut().push(format!("{count}: {event}"));
+        });
+
+        let log = Rc::clone(&seen);
+        let mut longest = String::new();
+        bus.subscribe(move |event: &String| {
+            if event.len() > longest.len() {
+                longest = event.clone();
+                log.borrow_mut().push(format!("longest: {longest}"));
+            }
+        });
+
+        for event in ["hi", "hello", "hey"] {
+            bus.publish(&event.to_owned());
+        }
+        assert_eq!(
+            *seen.borrow(),
+            [
+                "1: hi",
+                "longest: hi",
+                "2: hello",
+                "longest: hello",
+                "3: hey"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_closures_event_bus_unsubscribe() {
+        let total = Rc::new(RefCell::new(0));
+        let mut bus = EventBus::new();
+        let add = {
+            let total = Rc::clone(&total);
+            bus.subscribe(move |n: &i32| *total.borrow_mut() += n)
+        };
+        let double = {
+            let total = Rc::clone(&total);
+            bus.subscribe(move |n: &i32| *total.borrow_mut() += 2 * n)
+        };
+        assert_ne!(add, double);
+
+        bus.publish(&1);
+        assert_eq!(*total.borrow(), 3);
+        assert!(bus.unsubscribe(double));
+        bus.publish(&1);
+        assert_eq!(*total.borrow(), 4);
+        // Already gone.
+        assert!(!bus.unsubscribe(double));
+        assert_eq!(bus.len(), 1);
+
+        // Dropping the handler drops what it captured, so only we hold `total`.
+        assert!(bus.unsubscribe(add));
+        assert!(bus.is_empty());
+        assert_eq!(Rc::strong_count(&total), 1);
+    }
+
+    #[test]
+    fn test_closures_event_bus_once() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new();
+
+        // A FnOnce can give away what it captured: this moves `greeting` out.
+        let greeting = String::from("welcome");
+        let log = Rc::clone(&seen);
+        let once = bus.subscribe_once(move |name: &&str| {
+            log.borrow_mut().push(greeting + ", " + name);
+        });
+        let log = Rc::clone(&seen);
+        bus.subscribe(move |name: &&str| log.borrow_mut().push(format!("hi {name}")));
+
+        bus.publish(&"ann");
+        bus.publish(&"bob");
+        assert_eq!(*seen.borrow(), ["welcome, ann", "hi ann", "hi bob"]);
+        // It already ran, so there's nothing to unsubscribe.
+        assert!(!bus.unsubscribe(once));
+
+        // Unsubscribing before it runs means it never does.
+        let log = Rc::clone(&seen);
+        let never = bus.subscribe_once(move |_: &&str| log.borrow_mut().push("never".to_owned()));
+        assert!(bus.unsubscribe(never));
+        bus.publish(&"cy");
+        assert_eq!(seen.borrow().last().unwrap(), "hi cy");
+    }
+
+    #[test]
+    fn test_closures_event_bus_handler_panics() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut bus = EventBus::new();
+        let log = Rc::clone(&seen);
+        bus.subscribe(move |event: &&str| log.borrow_mut().push(format!("every {event}")));
+        bus.subscribe(|event: &&str| assert_ne!(*event, "boom"));
+        let log = Rc::clone(&seen);
+        bus.subscribe_once(move |event: &&str| log.borrow_mut().push(format!("once {event}")));
+
+        // AssertUnwindSafe, since the bus is used again after the panic.
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| bus.publish(&"boom")));
+        assert!(result.is_err());
+        // Only the handler that panicked is gone, and the one after it
+        // hasn't run yet.
+        assert_eq!(bus.len(), 2);
+        assert_eq!(*seen.borrow(), ["every boom"]);
+
+        bus.publish(&"ok");
+        assert_eq!(*seen.borrow(), ["every boom", "every ok", "once ok"]);
+    }
+
+    #[test]
+    fn exercise_closures_event_bus_borrowed() {
+        unimplemented!(
+            r"
+            EXERCISE: The handlers must be 'static, so they can't borrow
+            `count`; that's why the tests above need Rc<RefCell<..>>.  Make a
+            copy of EventBus with a lifetime, `EventBus<'a, E>`, whose handlers
+            only have to live for 'a.  Then uncomment the lines below.  Why
+            can't we read `count` until `bus` is gone?
+        "
+        );
+        // let mut count = 0;
+        // let mut bus = EventBus::new();
+        // bus.subscribe(|n: &i32| count += n);
+        // bus.publish(&2);
+        // bus.publish(&3);
+        // drop(bus);
+        // assert_eq!(count, 5);
+    }
+
+    #[test]
+    fn test_closures_memoize() {
+        // Unlike the event bus, Memoize can hold a closure that borrows, so
+        // the counter can be a plain local.  It's a Cell, since an `Fn` can't
+        // mutate what it captures.
+        let calls = Cell::new(0);
+        let square = Memoize::new(|n: u64| {
+            calls.set(calls.get() + 1);
+            n * n
+        });
+        assert!(square.is_empty());
+        assert_eq!(square.get(4), 16);
+        assert_eq!(square.get(4), 16);
+        assert_eq!(square.get(5), 25);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(square.len(), 2);
+
+        // Arguments just need to be hashable: cloned Strings work too.
+        let calls = Cell::new(0);
+        let shout = Memoize::new(|s: String| {
+            calls.set(calls.get() + 1);
+            s.to_uppercase()
+        });
+        for _ in 0..3 {
+            assert_eq!(shout.get("hey".to_owned()), "HEY");
+        }
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_closures_memoize_fibonacci() {
+        let calls = Cell::new(0);
+        let fib = MemoizeRecursive::new(|recurse: &dyn Fn(u64) -> u64, n: u64| {
+            calls.set(calls.get() + 1);
+            if n < 2 {
+                n
+            } else {
+                recurse(n - 1) + recurse(n - 2)
+            }
+        });
+        assert_eq!(fib.get(10), 55);
+        // Once for each of 0..=10, instead of 177 times without the cache.
+        assert_eq!(calls.get(), 11);
+        assert_eq!(fib.get(90), 2_880_067_194_370_816_120);
+        assert_eq!(calls.get(), 91);
+    }
+
+    #[test]
+    fn test_closures_lazy() {
+        let calls = Cell::new(0);
+        // `move` gives the String to the closure, and the closure gives it
+        // away when called: that's a FnOnce, like `f4` in
+        // `exercise_closures_fn_once`.
+        let name = String::from("config");
+        let lazy = Lazy::new(|| {
+            calls.set(calls.get() + 1);
+            name + ".toml"
+        });
+        assert!(!lazy.is_ready());
+        assert_eq!(calls.get(), 0);
+
+        assert_eq!(lazy.get(), "config.toml");
+        // Deref, so it can be used like the value itself.
+        assert_eq!(lazy.len(), 11);
+        assert!(lazy.is_ready());
+        assert_eq!(calls.get(), 1);
+
+        // A plain function works too, with the default `F`.
+        let empty: Lazy<Vec<i32>> = Lazy::new(Vec::new);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn exercise_closures_lazy_static() {
+        unimplemented!(
+            r"
+            EXERCISE: Uncomment the lines below.  They don't compile: a
+            `static` has to be shareable between threads, and `Lazy` isn't.
+            Why not?  Replace it with `std::sync::LazyLock`.  What does
+            LazyLock do that Lazy doesn't?
+        "
+        );
+        // static GREETING: Lazy<String> = Lazy::new(|| "hello".repeat(2));
+        // assert_eq!(*GREETING, "hellohello");
+    }
 }
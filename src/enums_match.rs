@@ -1,14 +1,26 @@
+// Color grows into something reusable in these modules; read them after this one.
+// Conversions to and from other color spaces:
+mod convert;
+// Parsing colors from strings like `#ff8000` or `rgb(255, 128, 0)`:
+mod parse;
+// Blending, and checking that text will be readable:
+mod contrast;
+// A capstone: reading and writing images, and shrinking their palettes:
+mod image;
+// Showing colors in the terminal, for `worksheet palette`:
+pub mod ansi;
+
 // You can make C-style enums.
-#[derive(Debug, PartialEq)]
-enum PrimaryColor {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrimaryColor {
     Red,
     Green,
     Blue,
 }
 
 // But the power lies in enums with different member types!
-#[derive(Debug, PartialEq)]
-enum Color {
+#[derive(Debug, Clone, PartialEq)]
+pub enum Color {
     Primary(PrimaryColor),
     Rgb(u8, u8, u8),
     Grey(f32),
@@ -0,0 +1,238 @@
+// Showing colors in a terminal.
+//
+// `color.swatch()` displays as a small block of the color, using ANSI escape
+// codes.  Terminals support 24-bit color, or the 256-color xterm palette, or
+// just 16 colors; for the last two, the nearest palette color is used.
+// `Other` colors have no RGB value, so their swatch is `??` in no color.
+
+use super::Color;
+
+/// Puts the terminal back to its normal colors.
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorDepth {
+    /// Guess what the terminal supports from `$COLORTERM` and `$TERM`.
+    pub fn detect(colorterm: Option<&str>, term: Option<&str>) -> ColorDepth {
+        match (colorterm, term) {
+            (Some("truecolor" | "24bit"), _) => ColorDepth::TrueColor,
+            (_, Some(term)) if term.contains("256color") => ColorDepth::Ansi256,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+}
+
+/// Displays a color as an ANSI swatch; see `Color::swatch`.
+#[derive(Debug, Clone, Copy)]
+pub struct Swatch<'a> {
+    color: &'a Color,
+    depth: ColorDepth,
+}
+
+impl Color {
+    /// A 24-bit swatch; use `.depth(...)` for older terminals.
+    pub fn swatch(&self) -> Swatch<'_> {
+        Swatch {
+            color: self,
+            depth: ColorDepth::TrueColor,
+        }
+    }
+}
+
+impl Swatch<'_> {
+    pub fn depth(self, depth: ColorDepth) -> Self {
+        Swatch { depth, ..self }
+    }
+}
+
+impl std::fmt::Display for Swatch<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some((r, g, b)) = self.color.to_rgb() else {
+            return f.write_str("??");
+        };
+        match self.depth {
+            ColorDepth::TrueColor => write!(f, "\x1b[48;2;{r};{g};{b}m")?,
+            ColorDepth::Ansi256 => write!(f, "\x1b[48;5;{}m", ansi256_index((r, g, b)))?,
+            ColorDepth::Ansi16 => {
+                // Background codes are 40-47, then 100-107 for the bright half.
+                let index = ansi16_index((r, g, b));
+                let code = if index < 8 { 40 + index } else { 92 + index };
+                write!(f, "\x1b[{code}m")?
+            }
+        }
+        write!(f, "  {RESET}")
+    }
+}
+
+/// xterm's defaults for the 16 basic colors; other terminals vary a little.
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// The levels of each channel in the 6x6x6 color cube (indices 16-231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> i32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// The nearest of the 16 basic colors.
+pub fn ansi16_index(rgb: (u8, u8, u8)) -> u8 {
+    (0..16)
+        .min_by_key(|&i| distance(rgb, ANSI16[i as usize]))
+        .unwrap_or(0)
+}
+
+/// The nearest color in the 256-color palette.  The first 16 are skipped,
+/// since terminals disagree about them; that leaves the color cube and the
+/// 24 greys (indices 232-255).
+pub fn ansi256_index(rgb: (u8, u8, u8)) -> u8 {
+    let cube = (0..216u8).map(|i| {
+        let level = |n: u8| CUBE_LEVELS[n as usize];
+        (16 + i, (level(i / 36), level(i / 6 % 6), level(i % 6)))
+    });
+    let greys = (0..24u8).map(|i| {
+        let level = 8 + 10 * i;
+        (232 + i, (level, level, level))
+    });
+    cube.chain(greys)
+        .min_by_key(|&(_, candidate)| distance(rgb, candidate))
+        .map_or(16, |(index, _)| index)
+}
+
+/// Colors for `worksheet palette` to show when it isn't given any.
+const DEFAULT_PALETTE: [&str; 8] = [
+    "red",
+    "green",
+    "blue",
+    "#ff8000",
+    "#8000ff",
+    "grey(0.25)",
+    "grey(0.75)",
+    "rebeccapurple",
+];
+
+/// `worksheet palette [--256 | --16] [color...]`: a swatch per color, then
+/// the color as given.  Without `--256` or `--16`, uses `depth`.
+pub fn palette_command(args: &[&str], depth: ColorDepth) -> Result<String, String> {
+    let (depth, colors) = match args {
+        ["--256", rest @ ..] => (ColorDepth::Ansi256, rest),
+        ["--16", rest @ ..] => (ColorDepth::Ansi16, rest),
+        rest => (depth, rest),
+    };
+    let colors = if colors.is_empty() {
+        &DEFAULT_PALETTE[..]
+    } else {
+        colors
+    };
+    let mut output = String::new();
+    for text in colors {
+        let color: Color = text
+            .parse()
+            .map_err(|err| format!("bad color `{text}`: {err}"))?;
+        output += &format!("{} {text}\n", color.swatch().depth(depth));
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::PrimaryColor;
+    use super::*;
+
+    #[test]
+    fn test_color_swatch_true_color() {
+        let orange = Color::Rgb(255, 128, 0);
+        assert_eq!(orange.swatch().to_string(), "\x1b[48;2;255;128;0m  \x1b[0m");
+        assert_eq!(
+            Color::Grey(0.5).swatch().to_string(),
+            "\x1b[48;2;128;128;128m  \x1b[0m"
+        );
+        let other = Color::Other {
+            desc: "octarine".to_owned(),
+        };
+        assert_eq!(other.swatch().to_string(), "??");
+    }
+
+    #[test]
+    fn test_color_swatch_256() {
+        let swatch = |color: Color| color.swatch().depth(ColorDepth::Ansi256).to_string();
+        assert_eq!(
+            swatch(Color::Primary(PrimaryColor::Red)),
+            "\x1b[48;5;196m  \x1b[0m"
+        );
+        // Mid grey is exactly on the grey ramp, and closer than any cube grey.
+        assert_eq!(swatch(Color::Grey(0.5)), "\x1b[48;5;244m  \x1b[0m");
+        assert_eq!(swatch(Color::Rgb(0, 0, 0)), "\x1b[48;5;16m  \x1b[0m");
+        assert_eq!(swatch(Color::Rgb(255, 255, 255)), "\x1b[48;5;231m  \x1b[0m");
+        // Orange lands between cube levels, and rounds to the nearest.
+        assert_eq!(ansi256_index((255, 128, 0)), 208);
+    }
+
+    #[test]
+    fn test_color_swatch_16() {
+        let swatch = |color: Color| color.swatch().depth(ColorDepth::Ansi16).to_string();
+        assert_eq!(swatch(Color::Rgb(0, 0, 0)), "\x1b[40m  \x1b[0m");
+        assert_eq!(swatch(Color::Rgb(200, 10, 10)), "\x1b[41m  \x1b[0m");
+        assert_eq!(
+            swatch(Color::Primary(PrimaryColor::Red)),
+            "\x1b[101m  \x1b[0m"
+        );
+        assert_eq!(swatch(Color::Grey(0.5)), "\x1b[100m  \x1b[0m");
+        assert_eq!(swatch(Color::Grey(1.0)), "\x1b[107m  \x1b[0m");
+    }
+
+    #[test]
+    fn test_color_depth_detect() {
+        use ColorDepth::*;
+        assert_eq!(ColorDepth::detect(Some("truecolor"), None), TrueColor);
+        assert_eq!(
+            ColorDepth::detect(Some("24bit"), Some("xterm-256color")),
+            TrueColor
+        );
+        assert_eq!(ColorDepth::detect(None, Some("xterm-256color")), Ansi256);
+        assert_eq!(ColorDepth::detect(Some("yes"), Some("xterm")), Ansi16);
+        assert_eq!(ColorDepth::detect(None, None), Ansi16);
+    }
+
+    #[test]
+    fn test_palette_command() {
+        assert_eq!(
+            palette_command(&["red", "#000"], ColorDepth::TrueColor),
+            Ok("\x1b[48;2;255;0;0m  \x1b[0m red\n\x1b[48;2;0;0;0m  \x1b[0m #000\n".to_owned())
+        );
+        assert_eq!(
+            palette_command(&["--16", "red"], ColorDepth::TrueColor),
+            Ok("\x1b[101m  \x1b[0m red\n".to_owned())
+        );
+        let defaults = palette_command(&["--256"], ColorDepth::TrueColor).unwrap();
+        assert_eq!(defaults.lines().count(), DEFAULT_PALETTE.len());
+        assert!(defaults.ends_with("?? rebeccapurple\n"));
+        assert_eq!(
+            palette_command(&["#12"], ColorDepth::TrueColor),
+            Err("bad color `#12`: hex colors need 3 or 6 digits, found 2 at col 1".to_owned())
+        );
+    }
+}
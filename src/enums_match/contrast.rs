@@ -0,0 +1,218 @@
+// Blending colors, and checking they're readable against each other.
+//
+// Luminance and contrast follow WCAG 2: contrast ratios run from 1 (no
+// contrast) to 21 (black on white).  Like the conversions, none of these work
+// for `Other`, so they return `None` for it.
+
+use super::convert::Hsl;
+use super::Color;
+
+/// The minimum contrast for normal text, at WCAG level AA.
+pub const WCAG_AA: f32 = 4.5;
+/// The minimum contrast for large text at level AA, or UI components.
+pub const WCAG_AA_LARGE: f32 = 3.0;
+/// The minimum contrast for normal text, at WCAG level AAA.
+pub const WCAG_AAA: f32 = 7.0;
+
+const BLACK: Color = Color::Rgb(0, 0, 0);
+const WHITE: Color = Color::Rgb(255, 255, 255);
+
+impl Color {
+    /// Blend towards `other`: `t` of 0.0 is `self`, 1.0 is `other`.
+    pub fn mix(&self, other: &Color, t: f32) -> Option<Color> {
+        let (r1, g1, b1) = self.to_rgb()?;
+        let (r2, g2, b2) = other.to_rgb()?;
+        let t = t.clamp(0.0, 1.0);
+        let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Some(Color::Rgb(lerp(r1, r2), lerp(g1, g2), lerp(b1, b2)))
+    }
+
+    /// Raise the HSL lightness by `amount`, up to white.
+    pub fn lighten(&self, amount: f32) -> Option<Color> {
+        let hsl = self.to_hsl()?;
+        Some(with_lightness(hsl, hsl.l + amount))
+    }
+
+    /// Lower the HSL lightness by `amount`, down to black.
+    pub fn darken(&self, amount: f32) -> Option<Color> {
+        self.lighten(-amount)
+    }
+
+    /// How bright the color looks, from 0.0 for black to 1.0 for white.
+    pub fn relative_luminance(&self) -> Option<f32> {
+        let (r, g, b) = self.to_rgb()?;
+        // Undo the sRGB gamma curve first, since luminance is linear.
+        let linear = |c: u8| {
+            let c = c as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        Some(0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b))
+    }
+
+    /// The WCAG contrast ratio; it's the same whichever way round they are.
+    pub fn contrast_ratio(&self, other: &Color) -> Option<f32> {
+        let a = self.relative_luminance()?;
+        let b = other.relative_luminance()?;
+        let (lighter, darker) = if a > b { (a, b) } else { (b, a) };
+        Some((lighter + 0.05) / (darker + 0.05))
+    }
+
+    /// The color closest to `self` that is readable on `background`.
+    ///
+    /// Only the lightness changes, so the hue stays recognizable.  If no
+    /// lightness is enough, this falls back to black or white, whichever
+    /// contrasts more.
+    pub fn nearest_accessible_foreground(
+        &self,
+        background: &Color,
+        min_ratio: f32,
+    ) -> Option<Color> {
+        let hsl = self.to_hsl()?;
+        background.to_rgb()?;
+        let readable = |color: &Color| color.contrast_ratio(background) >= Some(min_ratio);
+        if readable(self) {
+            return Some(self.clone());
+        }
+        // Step the lightness further and further away, trying darker first.
+        for step in 1..=100 {
+            let delta = step as f32 / 100.0;
+            for l in [hsl.l - delta, hsl.l + delta] {
+                if !(0.0..=1.0).contains(&l) {
+                    continue;
+                }
+                let candidate = with_lightness(hsl, l);
+                if readable(&candidate) {
+                    return Some(candidate);
+                }
+            }
+        }
+        if BLACK.contrast_ratio(background) > WHITE.contrast_ratio(background) {
+            Some(BLACK)
+        } else {
+            Some(WHITE)
+        }
+    }
+
+    /// Like `is_greyscale`, but lets the channels differ by up to `tolerance`,
+    /// since colors from photos or color pickers are rarely exact.
+    pub fn is_greyscale_within(&self, tolerance: u8) -> bool {
+        match self {
+            Color::Grey(_) => true,
+            Color::Rgb(r, g, b) => r.max(g).max(b) - r.min(g).min(b) <= tolerance,
+            Color::Primary(_) | Color::Other { .. } => false,
+        }
+    }
+}
+
+fn with_lightness(hsl: Hsl, l: f32) -> Color {
+    Hsl {
+        l: l.clamp(0.0, 1.0),
+        ..hsl
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::PrimaryColor;
+    use super::*;
+
+    fn octarine() -> Color {
+        Color::Other {
+            desc: "octarine".to_owned(),
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 0.01, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_color_mix() {
+        let red = Color::Primary(PrimaryColor::Red);
+        let blue = Color::Primary(PrimaryColor::Blue);
+        assert_eq!(red.mix(&blue, 0.0), Some(Color::Rgb(255, 0, 0)));
+        assert_eq!(red.mix(&blue, 0.5), Some(Color::Rgb(128, 0, 128)));
+        assert_eq!(red.mix(&blue, 1.0), Some(Color::Rgb(0, 0, 255)));
+        // t is clamped, so you can't overshoot.
+        assert_eq!(red.mix(&blue, 2.0), Some(Color::Rgb(0, 0, 255)));
+        assert_eq!(
+            Color::Grey(0.0).mix(&WHITE, 0.25),
+            Some(Color::Rgb(64, 64, 64))
+        );
+        assert_eq!(red.mix(&octarine(), 0.5), None);
+    }
+
+    #[test]
+    fn test_color_lighten_darken() {
+        let red = Color::Primary(PrimaryColor::Red);
+        assert_eq!(red.lighten(0.25), Some(Color::Rgb(255, 128, 128)));
+        assert_eq!(red.darken(0.25), Some(Color::Rgb(128, 0, 0)));
+        assert_eq!(red.lighten(1.0), Some(WHITE));
+        assert_eq!(red.darken(1.0), Some(BLACK));
+        assert_eq!(octarine().lighten(0.1), None);
+    }
+
+    #[test]
+    fn test_color_luminance_and_contrast() {
+        assert_eq!(BLACK.relative_luminance(), Some(0.0));
+        assert_close(WHITE.relative_luminance().unwrap(), 1.0);
+        assert_close(BLACK.contrast_ratio(&WHITE).unwrap(), 21.0);
+        assert_eq!(BLACK.contrast_ratio(&WHITE), WHITE.contrast_ratio(&BLACK));
+        assert_close(WHITE.contrast_ratio(&WHITE).unwrap(), 1.0);
+        // The classic "just misses AA" grey.
+        let grey = Color::Rgb(0x77, 0x77, 0x77);
+        assert_close(grey.contrast_ratio(&WHITE).unwrap(), 4.48);
+        assert_eq!(grey.contrast_ratio(&octarine()), None);
+    }
+
+    #[test]
+    fn test_color_nearest_accessible_foreground() {
+        // Already fine, so left alone.
+        assert_eq!(
+            BLACK.nearest_accessible_foreground(&WHITE, WCAG_AAA),
+            Some(BLACK)
+        );
+
+        let grey = Color::Rgb(0x77, 0x77, 0x77);
+        let fixed = grey.nearest_accessible_foreground(&WHITE, WCAG_AA).unwrap();
+        assert!(fixed.contrast_ratio(&WHITE).unwrap() >= WCAG_AA);
+        // It should only be a nudge darker, and still grey.
+        assert_eq!(fixed, Color::Rgb(0x74, 0x74, 0x74));
+
+        // Red on dark blue has to get lighter, and stays red-ish.
+        let red = Color::Primary(PrimaryColor::Red);
+        let navy = Color::Rgb(0, 0, 128);
+        let fixed = red.nearest_accessible_foreground(&navy, WCAG_AA).unwrap();
+        assert!(fixed.contrast_ratio(&navy).unwrap() >= WCAG_AA);
+        let (r, g, b) = fixed.to_rgb().unwrap();
+        assert!(r == 255 && g == b && g > 0, "{fixed:?}");
+
+        // Nothing reaches 7:1 on mid grey, so fall back to black, the better of the two.
+        let mid = Color::Grey(0.5);
+        assert_eq!(
+            red.nearest_accessible_foreground(&mid, WCAG_AAA),
+            Some(BLACK)
+        );
+        assert_eq!(
+            red.nearest_accessible_foreground(&octarine(), WCAG_AA),
+            None
+        );
+    }
+
+    #[test]
+    fn test_color_is_greyscale_within() {
+        let nearly = Color::Rgb(120, 122, 119);
+        assert!(!nearly.is_greyscale());
+        assert!(!nearly.is_greyscale_within(2));
+        assert!(nearly.is_greyscale_within(3));
+        assert!(Color::Rgb(7, 7, 7).is_greyscale_within(0));
+        assert!(Color::Grey(0.3).is_greyscale_within(0));
+        assert!(!Color::Primary(PrimaryColor::Red).is_greyscale_within(255));
+        assert!(!octarine().is_greyscale_within(255));
+    }
+}
@@ -0,0 +1,358 @@
+// Converting `Color` to and from RGB, HSL and HSV.
+//
+// RGB channels are 0-255.  For HSL and HSV, hue is in degrees (0 up to 360)
+// and the rest are fractions from 0.0 to 1.0.  `Grey(scale)` uses the same
+// 0.0 (black) to 1.0 (white) scale.
+
+use super::{Color, PrimaryColor};
+
+/// Hue, saturation, lightness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f32,
+    pub s: f32,
+    pub l: f32,
+}
+
+/// Hue, saturation, value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ColorConversionError {
+    #[error("color `{0}` has no known RGB value")]
+    NoRgb(String),
+}
+
+impl PrimaryColor {
+    pub fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            PrimaryColor::Red => (255, 0, 0),
+            PrimaryColor::Green => (0, 255, 0),
+            PrimaryColor::Blue => (0, 0, 255),
+        }
+    }
+}
+
+impl Color {
+    /// The RGB channels of this color, if it has any.  `Other` doesn't.
+    pub fn to_rgb(&self) -> Option<(u8, u8, u8)> {
+        match self {
+            Color::Primary(primary) => Some(primary.to_rgb()),
+            Color::Rgb(r, g, b) => Some((*r, *g, *b)),
+            Color::Grey(scale) => {
+                let channel = unit_to_channel(*scale);
+                Some((channel, channel, channel))
+            }
+            Color::Other { .. } => None,
+        }
+    }
+
+    pub fn to_hsl(&self) -> Option<Hsl> {
+        self.to_rgb().map(Hsl::from)
+    }
+
+    pub fn to_hsv(&self) -> Option<Hsv> {
+        self.to_rgb().map(Hsv::from)
+    }
+}
+
+fn unit_to_channel(x: f32) -> u8 {
+    (x.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn channel_to_unit(c: u8) -> f32 {
+    c as f32 / 255.0
+}
+
+/// The hue in degrees, and the max and min channels, all shared by HSL and HSV.
+fn hue_max_min((r, g, b): (u8, u8, u8)) -> (f32, f32, f32) {
+    let (r, g, b) = (channel_to_unit(r), channel_to_unit(g), channel_to_unit(b));
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        // Any hue works for greys; 0 is conventional.
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue, max, min)
+}
+
+/// Given hue and chroma, and the amount to add to every channel, build RGB.
+fn from_hue_chroma(h: f32, chroma: f32, m: f32) -> (u8, u8, u8) {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    (
+        unit_to_channel(r + m),
+        unit_to_channel(g + m),
+        unit_to_channel(b + m),
+    )
+}
+
+impl From<(u8, u8, u8)> for Hsl {
+    fn from(rgb: (u8, u8, u8)) -> Self {
+        let (h, max, min) = hue_max_min(rgb);
+        let l = (max + min) / 2.0;
+        let s = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        Hsl { h, s, l }
+    }
+}
+
+impl From<Hsl> for (u8, u8, u8) {
+    fn from(Hsl { h, s, l }: Hsl) -> Self {
+        let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        from_hue_chroma(h, chroma, l - chroma / 2.0)
+    }
+}
+
+impl From<(u8, u8, u8)> for Hsv {
+    fn from(rgb: (u8, u8, u8)) -> Self {
+        let (h, max, min) = hue_max_min(rgb);
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        Hsv { h, s, v: max }
+    }
+}
+
+impl From<Hsv> for (u8, u8, u8) {
+    fn from(Hsv { h, s, v }: Hsv) -> Self {
+        let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+        let chroma = v * s;
+        from_hue_chroma(h, chroma, v - chroma)
+    }
+}
+
+impl From<Hsl> for Hsv {
+    fn from(Hsl { h, s, l }: Hsl) -> Self {
+        let v = l + s * l.min(1.0 - l);
+        let s = if v == 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) };
+        Hsv { h, s, v }
+    }
+}
+
+impl From<Hsv> for Hsl {
+    fn from(Hsv { h, s, v }: Hsv) -> Self {
+        let l = v * (1.0 - s / 2.0);
+        let s = if l == 0.0 || l == 1.0 {
+            0.0
+        } else {
+            (v - l) / l.min(1.0 - l)
+        };
+        Hsl { h, s, l }
+    }
+}
+
+impl From<PrimaryColor> for Color {
+    fn from(primary: PrimaryColor) -> Self {
+        Color::Primary(primary)
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Color::Rgb(r, g, b)
+    }
+}
+
+impl From<Hsl> for Color {
+    fn from(hsl: Hsl) -> Self {
+        <(u8, u8, u8)>::from(hsl).into()
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(hsv: Hsv) -> Self {
+        <(u8, u8, u8)>::from(hsv).into()
+    }
+}
+
+impl TryFrom<&Color> for (u8, u8, u8) {
+    type Error = ColorConversionError;
+
+    fn try_from(color: &Color) -> Result<Self, Self::Error> {
+        color
+            .to_rgb()
+            .ok_or_else(|| ColorConversionError::NoRgb(color.describe()))
+    }
+}
+
+impl TryFrom<&Color> for Hsl {
+    type Error = ColorConversionError;
+
+    fn try_from(color: &Color) -> Result<Self, Self::Error> {
+        <(u8, u8, u8)>::try_from(color).map(Hsl::from)
+    }
+}
+
+impl TryFrom<&Color> for Hsv {
+    type Error = ColorConversionError;
+
+    fn try_from(color: &Color) -> Result<Self, Self::Error> {
+        <(u8, u8, u8)>::try_from(color).map(Hsv::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-3;
+
+    fn assert_close(a: f32, b: f32, what: &str) {
+        assert!((a - b).abs() < EPSILON, "{what}: {a} != {b}");
+    }
+
+    /// A spread of colors over the whole cube, including every corner.
+    fn sample_rgbs() -> impl Iterator<Item = (u8, u8, u8)> {
+        let steps = [0, 1, 37, 64, 127, 128, 200, 254, 255];
+        steps.into_iter().flat_map(move |r| {
+            steps
+                .into_iter()
+                .flat_map(move |g| steps.into_iter().map(move |b| (r, g, b)))
+        })
+    }
+
+    #[test]
+    fn test_color_to_rgb() {
+        assert_eq!(
+            Color::Primary(PrimaryColor::Red).to_rgb(),
+            Some((255, 0, 0))
+        );
+        assert_eq!(
+            Color::Primary(PrimaryColor::Green).to_rgb(),
+            Some((0, 255, 0))
+        );
+        assert_eq!(
+            Color::Primary(PrimaryColor::Blue).to_rgb(),
+            Some((0, 0, 255))
+        );
+        assert_eq!(Color::Rgb(1, 2, 3).to_rgb(), Some((1, 2, 3)));
+        assert_eq!(Color::Grey(0.0).to_rgb(), Some((0, 0, 0)));
+        assert_eq!(Color::Grey(0.5).to_rgb(), Some((128, 128, 128)));
+        assert_eq!(Color::Grey(1.0).to_rgb(), Some((255, 255, 255)));
+        // Out-of-range greys are clamped rather than wrapping around.
+        assert_eq!(Color::Grey(7.0).to_rgb(), Some((255, 255, 255)));
+        let other = Color::Other {
+            desc: "octarine".to_owned(),
+        };
+        assert_eq!(other.to_rgb(), None);
+    }
+
+    #[test]
+    fn test_color_known_hsl_hsv() {
+        let red = Color::Primary(PrimaryColor::Red);
+        assert_eq!(
+            red.to_hsl(),
+            Some(Hsl {
+                h: 0.0,
+                s: 1.0,
+                l: 0.5
+            })
+        );
+        assert_eq!(
+            red.to_hsv(),
+            Some(Hsv {
+                h: 0.0,
+                s: 1.0,
+                v: 1.0
+            })
+        );
+
+        let teal = Hsl::from((0, 128, 128));
+        assert_close(teal.h, 180.0, "h");
+        assert_close(teal.s, 1.0, "s");
+        assert_close(teal.l, 128.0 / 255.0 / 2.0, "l");
+
+        let grey = Color::Grey(0.5).to_hsv().unwrap();
+        assert_eq!((grey.h, grey.s), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_color_rgb_round_trips() {
+        // RGB -> HSx -> RGB is exact, once rounded back to whole channels.
+        for rgb in sample_rgbs() {
+            assert_eq!(<(u8, u8, u8)>::from(Hsl::from(rgb)), rgb, "via HSL");
+            assert_eq!(<(u8, u8, u8)>::from(Hsv::from(rgb)), rgb, "via HSV");
+        }
+    }
+
+    #[test]
+    fn test_color_hsl_hsv_round_trips() {
+        for rgb in sample_rgbs() {
+            let hsl = Hsl::from(rgb);
+            let back = Hsl::from(Hsv::from(hsl));
+            assert_close(back.l, hsl.l, "l");
+            // Saturation is meaningless for black and white.
+            if hsl.l > EPSILON && hsl.l < 1.0 - EPSILON {
+                assert_close(back.s, hsl.s, "s");
+            }
+            assert_close(back.h, hsl.h, "h");
+
+            let hsv = Hsv::from(rgb);
+            let direct = Hsv::from(hsl);
+            assert_close(direct.v, hsv.v, "v");
+            if hsv.v > EPSILON {
+                assert_close(direct.s, hsv.s, "s");
+            }
+        }
+    }
+
+    #[test]
+    fn test_color_from_impls() {
+        assert_eq!(
+            Color::from(PrimaryColor::Blue),
+            Color::Primary(PrimaryColor::Blue)
+        );
+        assert_eq!(Color::from((1, 2, 3)), Color::Rgb(1, 2, 3));
+        let yellow: Color = Hsv {
+            h: 60.0,
+            s: 1.0,
+            v: 1.0,
+        }
+        .into();
+        assert_eq!(yellow, Color::Rgb(255, 255, 0));
+        // Hue wraps around, so 420 degrees is the same as 60.
+        let also_yellow: Color = Hsl {
+            h: 420.0,
+            s: 1.0,
+            l: 0.5,
+        }
+        .into();
+        assert_eq!(also_yellow, yellow);
+    }
+
+    #[test]
+    fn test_color_try_from() {
+        let green = Color::Primary(PrimaryColor::Green);
+        assert_eq!(<(u8, u8, u8)>::try_from(&green), Ok((0, 255, 0)));
+        assert_eq!(Hsl::try_from(&green).map(|hsl| hsl.h), Ok(120.0));
+        let other = Color::Other {
+            desc: "octarine".to_owned(),
+        };
+        let err = Hsv::try_from(&other).unwrap_err();
+        assert_eq!(err, ColorConversionError::NoRgb("octarine".to_owned()));
+        assert_eq!(err.to_string(), "color `octarine` has no known RGB value");
+    }
+}
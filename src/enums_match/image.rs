@@ -0,0 +1,718 @@
+// A capstone: images made of `Color`s.
+//
+// Reads and writes the Netpbm formats, which are simple enough to parse by
+// hand: a magic number, the width, height and maximum sample value, then the
+// samples, either as decimal text ("plain") or as bytes ("raw").
+//
+//     P2 / P5: greyscale (PGM), one sample per pixel
+//     P3 / P6: color (PPM), three samples per pixel
+//
+// Pixels are stored row by row, left to right, in one `Vec<Color>`.  Greys
+// come back as `Color::Grey` and colors as `Color::Rgb`, so all the matching in
+// the parent module works on them.
+
+use std::io;
+use std::path::Path;
+
+use super::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    PlainPgm,
+    PlainPpm,
+    RawPgm,
+    RawPpm,
+}
+
+impl Format {
+    fn magic(self) -> &'static str {
+        match self {
+            Format::PlainPgm => "P2",
+            Format::PlainPpm => "P3",
+            Format::RawPgm => "P5",
+            Format::RawPpm => "P6",
+        }
+    }
+
+    fn from_magic(magic: &[u8]) -> Option<Format> {
+        match magic {
+            b"P2" => Some(Format::PlainPgm),
+            b"P3" => Some(Format::PlainPpm),
+            b"P5" => Some(Format::RawPgm),
+            b"P6" => Some(Format::RawPpm),
+            _ => None,
+        }
+    }
+
+    fn is_raw(self) -> bool {
+        matches!(self, Format::RawPgm | Format::RawPpm)
+    }
+
+    fn channels(self) -> usize {
+        match self {
+            Format::PlainPgm | Format::RawPgm => 1,
+            Format::PlainPpm | Format::RawPpm => 3,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImageError {
+    #[error("could not read or write the image")]
+    Io(#[from] io::Error),
+    #[error("not a PGM or PPM file")]
+    BadMagic,
+    #[error("expected {what} at byte {offset}")]
+    Expected { what: &'static str, offset: usize },
+    #[error("sample {value} is more than the maximum {maxval}, at byte {offset}")]
+    SampleOutOfRange {
+        value: u32,
+        maxval: u32,
+        offset: usize,
+    },
+    #[error("image ends early: expected {expected} samples, found {found}")]
+    Truncated { expected: usize, found: usize },
+    #[error("{pixels} pixels can't make a {width}x{height} image")]
+    SizeMismatch {
+        pixels: usize,
+        width: usize,
+        height: usize,
+    },
+    #[error("pixel ({x}, {y}) is `{desc}`, which has no RGB value")]
+    NoRgb { x: usize, y: usize, desc: String },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Color {
+    /// The grey with the same brightness, using the usual (Rec. 601) weights.
+    pub fn to_grey(&self) -> Option<Color> {
+        match self {
+            Color::Grey(scale) => Some(Color::Grey(*scale)),
+            _ => {
+                let (r, g, b) = self.to_rgb()?;
+                let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+                Some(Color::Grey(luma / 255.0))
+            }
+        }
+    }
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, fill: Color) -> Self {
+        Image {
+            width,
+            height,
+            pixels: vec![fill; width * height],
+        }
+    }
+
+    pub fn from_pixels(
+        width: usize,
+        height: usize,
+        pixels: Vec<Color>,
+    ) -> Result<Self, ImageError> {
+        if width.checked_mul(height) != Some(pixels.len()) {
+            return Err(ImageError::SizeMismatch {
+                pixels: pixels.len(),
+                width,
+                height,
+            });
+        }
+        Ok(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&Color> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.pixels.get(y * self.width + x)
+    }
+
+    /// Panics if `(x, y)` is outside the image, like indexing a `Vec`.
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        assert!(
+            x < self.width && y < self.height,
+            "({x}, {y}) is outside the image"
+        );
+        self.pixels[y * self.width + x] = color;
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[Color]> + '_ {
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Image, ImageError> {
+        Decoder { bytes, pos: 0 }.image()
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Image, ImageError> {
+        Image::decode(&std::fs::read(path)?)
+    }
+
+    /// Writes with a maximum sample value of 255.  PGM formats write each
+    /// pixel's grey; PPM formats fail on `Other` pixels.
+    pub fn encode(&self, format: Format) -> Result<Vec<u8>, ImageError> {
+        let mut samples = Vec::with_capacity(self.pixels.len() * format.channels());
+        for (i, color) in self.pixels.iter().enumerate() {
+            let rgb = match format.channels() {
+                1 => color.to_grey().and_then(|grey| grey.to_rgb()),
+                _ => color.to_rgb(),
+            };
+            let Some((r, g, b)) = rgb else {
+                return Err(ImageError::NoRgb {
+                    x: i % self.width,
+                    y: i / self.width,
+                    desc: color.describe(),
+                });
+            };
+            match format.channels() {
+                1 => samples.push(r),
+                _ => samples.extend([r, g, b]),
+            }
+        }
+
+        let mut out =
+            format!("{}\n{} {}\n255\n", format.magic(), self.width, self.height).into_bytes();
+        if format.is_raw() {
+            out.extend(samples);
+        } else {
+            // Plain files shouldn't have lines longer than 70 characters.
+            let mut line_len = 0;
+            for sample in samples {
+                let text = sample.to_string();
+                if line_len > 0 && line_len + 1 + text.len() > 70 {
+                    out.push(b'\n');
+                    line_len = 0;
+                } else if line_len > 0 {
+                    out.push(b' ');
+                    line_len += 1;
+                }
+                out.extend(text.as_bytes());
+                line_len += text.len();
+            }
+            out.push(b'\n');
+        }
+        Ok(out)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>, format: Format) -> Result<(), ImageError> {
+        std::fs::write(path, self.encode(format)?)?;
+        Ok(())
+    }
+
+    /// Every pixel replaced by its grey; `Other` pixels are left alone.
+    pub fn to_greyscale(&self) -> Image {
+        let pixels = self
+            .pixels
+            .iter()
+            .map(|color| color.to_grey().unwrap_or_else(|| color.clone()))
+            .collect();
+        Image { pixels, ..*self }
+    }
+
+    /// Up to `n` colors that represent the image well, found by median cut:
+    /// start with one box around every pixel's color, and keep splitting the
+    /// box that's widest in any channel at its median, until there are `n`
+    /// boxes.  Each box's average is one palette color.
+    pub fn palette(&self, n: usize) -> Vec<Color> {
+        let rgbs: Vec<[u8; 3]> = self
+            .pixels
+            .iter()
+            .filter_map(Color::to_rgb)
+            .map(|(r, g, b)| [r, g, b])
+            .collect();
+        if rgbs.is_empty() || n == 0 {
+            return Vec::new();
+        }
+
+        let mut boxes = vec![rgbs];
+        while boxes.len() < n {
+            let widest = boxes
+                .iter()
+                .enumerate()
+                .flat_map(|(i, rgbs)| {
+                    (0..3).map(move |channel| (i, channel, spread(rgbs, channel)))
+                })
+                .filter(|&(_, _, spread)| spread > 0)
+                .max_by_key(|&(_, _, spread)| spread);
+            // Every box is a single color, so there's nothing left to split.
+            let Some((i, channel, _)) = widest else {
+                break;
+            };
+            let mut lower = boxes.swap_remove(i);
+            lower.sort_by_key(|rgb| rgb[channel]);
+            // Split where the channel's value changes, so a value that's
+            // repeated across the median doesn't end up in both halves: just
+            // before the median's run of values, or just after, whichever is
+            // closer.  The spread isn't 0, so at least one is inside the box.
+            let mid = lower.len() / 2;
+            let median = lower[mid][channel];
+            let start = lower.partition_point(|rgb| rgb[channel] < median);
+            let end = lower.partition_point(|rgb| rgb[channel] <= median);
+            let split = match start > 0 && (end == lower.len() || mid - start <= end - mid) {
+                true => start,
+                false => end,
+            };
+            let upper = lower.split_off(split);
+            boxes.push(lower);
+            boxes.push(upper);
+        }
+
+        let mut palette: Vec<(u8, u8, u8)> = boxes.iter().map(|rgbs| average(rgbs)).collect();
+        palette.sort();
+        // Different boxes can still happen to average to the same color.
+        palette.dedup();
+        palette.into_iter().map(Color::from).collect()
+    }
+
+    /// The image redrawn with only the colors from `palette(n)`.
+    pub fn quantize(&self, n: usize) -> Image {
+        let palette = self.palette(n);
+        let pixels = self
+            .pixels
+            .iter()
+            .map(|color| match color.to_rgb() {
+                Some(rgb) => nearest(&palette, rgb).clone(),
+                None => color.clone(),
+            })
+            .collect();
+        Image { pixels, ..*self }
+    }
+}
+
+fn spread(rgbs: &[[u8; 3]], channel: usize) -> u8 {
+    let max = rgbs.iter().map(|rgb| rgb[channel]).max().unwrap_or(0);
+    let min = rgbs.iter().map(|rgb| rgb[channel]).min().unwrap_or(0);
+    max - min
+}
+
+fn average(rgbs: &[[u8; 3]]) -> (u8, u8, u8) {
+    let len = rgbs.len() as u32;
+    let mean = |channel: usize| {
+        let sum: u32 = rgbs.iter().map(|rgb| rgb[channel] as u32).sum();
+        ((sum + len / 2) / len) as u8
+    };
+    (mean(0), mean(1), mean(2))
+}
+
+/// The palette color closest to `rgb`.  `palette` must not be empty.
+fn nearest(palette: &[Color], (r, g, b): (u8, u8, u8)) -> &Color {
+    let distance = |color: &&Color| {
+        let (pr, pg, pb) = color.to_rgb().unwrap_or_default();
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, pr) + d(g, pg) + d(b, pb)
+    };
+    palette
+        .iter()
+        .min_by_key(distance)
+        .expect("palette is empty")
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn image(mut self) -> Result<Image, ImageError> {
+        let format = self
+            .bytes
+            .get(..2)
+            .and_then(Format::from_magic)
+            .ok_or(ImageError::BadMagic)?;
+        self.pos = 2;
+        let width = self.header_number("a width")? as usize;
+        let height = self.header_number("a height")? as usize;
+        self.skip_whitespace();
+        let maxval_offset = self.pos;
+        let maxval = self.header_number("a maximum value")?;
+        if !(1..=65535).contains(&maxval) {
+            return Err(ImageError::Expected {
+                what: "a maximum value from 1 to 65535",
+                offset: maxval_offset,
+            });
+        }
+        let count = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(format.channels()))
+            .ok_or(ImageError::Expected {
+                what: "a smaller image",
+                offset: maxval_offset,
+            })?;
+
+        let samples = if format.is_raw() {
+            // Exactly one whitespace byte separates the header from the data.
+            match self.bytes.get(self.pos) {
+                Some(byte) if byte.is_ascii_whitespace() => self.pos += 1,
+                _ => {
+                    return Err(ImageError::Expected {
+                        what: "whitespace after the header",
+                        offset: self.pos,
+                    })
+                }
+            }
+            self.raw_samples(count, maxval)?
+        } else {
+            self.plain_samples(count, maxval)?
+        };
+
+        let pixels = match format.channels() {
+            1 => samples
+                .iter()
+                .map(|&value| Color::Grey(value as f32 / maxval as f32))
+                .collect(),
+            _ => {
+                // Scale to 0-255, rounding to nearest.
+                let scale = |value: u32| ((value * 255 + maxval / 2) / maxval) as u8;
+                samples
+                    .chunks(3)
+                    .map(|rgb| Color::Rgb(scale(rgb[0]), scale(rgb[1]), scale(rgb[2])))
+                    .collect()
+            }
+        };
+        Image::from_pixels(width, height, pixels)
+    }
+
+    /// Skips whitespace, and comments, which run from `#` to the end of the line.
+    fn skip_whitespace(&mut self) {
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            if byte == b'#' {
+                while self.bytes.get(self.pos).is_some_and(|&byte| byte != b'\n') {
+                    self.pos += 1;
+                }
+            } else if byte.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// The next decimal number and where it started, or `None` if there isn't one.
+    fn number(&mut self) -> Option<(u32, usize)> {
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.bytes.get(self.pos).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+        let digits = std::str::from_utf8(&self.bytes[start..self.pos]).ok()?;
+        match digits.parse() {
+            Ok(value) => Some((value, start)),
+            Err(_) => {
+                self.pos = start;
+                None
+            }
+        }
+    }
+
+    fn header_number(&mut self, what: &'static str) -> Result<u32, ImageError> {
+        match self.number() {
+            Some((value, _)) => Ok(value),
+            None => Err(ImageError::Expected {
+                what,
+                offset: self.pos,
+            }),
+        }
+    }
+
+    fn plain_samples(&mut self, count: usize, maxval: u32) -> Result<Vec<u32>, ImageError> {
+        // `count` comes from the header, so don't trust it to size the Vec:
+        // each sample takes at least a byte, so the input bounds it too.
+        let mut samples = Vec::with_capacity(count.min(self.bytes.len() - self.pos));
+        while samples.len() < count {
+            let Some((value, offset)) = self.number() else {
+                if self.pos == self.bytes.len() {
+                    return Err(ImageError::Truncated {
+                        expected: count,
+                        found: samples.len(),
+                    });
+                }
+                return Err(ImageError::Expected {
+                    what: "a sample",
+                    offset: self.pos,
+                });
+            };
+            check_sample(value, maxval, offset)?;
+            samples.push(value);
+        }
+        Ok(samples)
+    }
+
+    fn raw_samples(&mut self, count: usize, maxval: u32) -> Result<Vec<u32>, ImageError> {
+        // Samples are one byte each, or two (big-endian) if they can be over 255.
+        let width = if maxval < 256 { 1 } else { 2 };
+        let data = &self.bytes[self.pos..];
+        // Divide rather than multiply, so a huge `count` can't overflow.
+        if data.len() / width < count {
+            return Err(ImageError::Truncated {
+                expected: count,
+                found: data.len() / width,
+            });
+        }
+        data[..count * width]
+            .chunks(width)
+            .enumerate()
+            .map(|(i, bytes)| {
+                let value = bytes.iter().fold(0, |acc, &byte| acc << 8 | byte as u32);
+                check_sample(value, maxval, self.pos + i * width)?;
+                Ok(value)
+            })
+            .collect()
+    }
+}
+
+fn check_sample(value: u32, maxval: u32, offset: usize) -> Result<(), ImageError> {
+    if value > maxval {
+        return Err(ImageError::SampleOutOfRange {
+            value,
+            maxval,
+            offset,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::PrimaryColor;
+    use super::*;
+
+    const FLAG: &[u8] = include_bytes!("fixtures/flag.ppm");
+    const FLAG_RAW: &[u8] = include_bytes!("fixtures/flag_raw.ppm");
+    const RAMP: &[u8] = include_bytes!("fixtures/ramp.pgm");
+    const RAMP_RAW: &[u8] = include_bytes!("fixtures/ramp_raw.pgm");
+    const TWO_TONE: &[u8] = include_bytes!("fixtures/two_tone.ppm");
+    const MOSTLY_RED: &[u8] = include_bytes!("fixtures/mostly_red.ppm");
+
+    fn flag() -> Image {
+        let pixels = vec![
+            Color::Rgb(255, 0, 0),
+            Color::Rgb(255, 255, 255),
+            Color::Rgb(0, 0, 255),
+            Color::Rgb(0, 0, 0),
+            Color::Rgb(128, 128, 128),
+            Color::Rgb(255, 255, 0),
+        ];
+        Image::from_pixels(3, 2, pixels).unwrap()
+    }
+
+    #[test]
+    fn test_image_decode_ppm() {
+        assert_eq!(Image::decode(FLAG).unwrap(), flag());
+        assert_eq!(Image::decode(FLAG_RAW).unwrap(), flag());
+        let image = flag();
+        assert_eq!(image.get(2, 0), Some(&Color::Rgb(0, 0, 255)));
+        assert_eq!(image.get(3, 0), None);
+        let rows: Vec<&[Color]> = image.rows().collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1][0], Color::Rgb(0, 0, 0));
+    }
+
+    #[test]
+    fn test_image_decode_pgm() {
+        let image = Image::decode(RAMP).unwrap();
+        assert_eq!((image.width(), image.height()), (4, 2));
+        assert_eq!(image.get(1, 0), Some(&Color::Grey(5.0 / 15.0)));
+        // Greys are rescaled from the file's maximum, so both files agree.
+        assert_eq!(Image::decode(RAMP_RAW).unwrap(), image);
+        assert!(image.pixels().iter().all(Color::is_greyscale));
+    }
+
+    #[test]
+    fn test_image_decode_sixteen_bit() {
+        let mut bytes = b"P5 2 1 1000\n".to_vec();
+        bytes.extend([0x01, 0xf4, 0x03, 0xe8]);
+        let image = Image::decode(&bytes).unwrap();
+        assert_eq!(image.pixels(), [Color::Grey(0.5), Color::Grey(1.0)]);
+    }
+
+    #[test]
+    fn test_image_encode() {
+        // Raw fixtures are written the way we write them, byte for byte.
+        assert_eq!(flag().encode(Format::RawPpm).unwrap(), FLAG_RAW);
+        let ramp = Image::decode(RAMP).unwrap();
+        assert_eq!(ramp.encode(Format::RawPgm).unwrap(), RAMP_RAW);
+        assert_eq!(
+            String::from_utf8(ramp.encode(Format::PlainPgm).unwrap()).unwrap(),
+            "P2\n4 2\n255\n0 85 170 255 255 170 85 0\n"
+        );
+        for format in [Format::PlainPpm, Format::RawPpm] {
+            let bytes = flag().encode(format).unwrap();
+            assert_eq!(Image::decode(&bytes).unwrap(), flag());
+        }
+    }
+
+    #[test]
+    fn test_image_encode_wraps_plain_lines() {
+        let image = Image::new(30, 1, Color::Rgb(255, 255, 255));
+        let bytes = image.encode(Format::PlainPpm).unwrap();
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert!(text.lines().all(|line| line.len() <= 70));
+        assert_eq!(Image::decode(&bytes).unwrap(), image);
+    }
+
+    #[test]
+    fn test_image_save_and_open() {
+        let path = std::env::temp_dir().join(format!("flag-{}.ppm", std::process::id()));
+        flag().save(&path, Format::RawPpm).unwrap();
+        let opened = Image::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(opened.unwrap(), flag());
+
+        let err = Image::open(&path).unwrap_err();
+        assert!(matches!(err, ImageError::Io(_)), "{err:?}");
+    }
+
+    #[test]
+    fn test_image_decode_errors() {
+        let err = |bytes: &[u8]| Image::decode(bytes).unwrap_err().to_string();
+        assert_eq!(err(b"GIF89a"), "not a PGM or PPM file");
+        assert_eq!(err(b"P3 2 x"), "expected a height at byte 5");
+        assert_eq!(
+            err(b"P3 1 1 0\n"),
+            "expected a maximum value from 1 to 65535 at byte 7"
+        );
+        assert_eq!(
+            err(b"P6 1 1 255"),
+            "expected whitespace after the header at byte 10"
+        );
+        assert_eq!(
+            err(b"P2 2 1 9\n3 10"),
+            "sample 10 is more than the maximum 9, at byte 11"
+        );
+        assert_eq!(
+            err(b"P2 2 1 9\n3 # and then nothing"),
+            "image ends early: expected 2 samples, found 1"
+        );
+        assert_eq!(err(b"P2 2 1 9\n3 -4"), "expected a sample at byte 11");
+        assert_eq!(
+            err(b"P6 2 1 255\n\x01\x02\x03"),
+            "image ends early: expected 6 samples, found 3"
+        );
+        // A tiny file can claim a huge image; that's an error, not an
+        // attempt to allocate gigabytes.
+        assert_eq!(
+            err(b"P3 100000 100000 255\n1 2 3"),
+            "image ends early: expected 30000000000 samples, found 3"
+        );
+        assert_eq!(
+            err(b"P6 100000 100000 65535\n\x01\x02"),
+            "image ends early: expected 30000000000 samples, found 1"
+        );
+    }
+
+    #[test]
+    fn test_image_encode_needs_rgb() {
+        let mut image = flag();
+        image.set(
+            1,
+            1,
+            Color::Other {
+                desc: "octarine".to_owned(),
+            },
+        );
+        let err = image.encode(Format::RawPgm).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "pixel (1, 1) is `octarine`, which has no RGB value"
+        );
+        assert!(matches!(
+            Image::from_pixels(2, 2, vec![Color::Grey(0.0)]),
+            Err(ImageError::SizeMismatch { pixels: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_image_to_greyscale() {
+        let grey = flag().to_greyscale();
+        assert!(grey.pixels().iter().all(Color::is_greyscale));
+        // White and black stay put, and pure blue is darker than pure red.
+        assert_eq!(grey.get(1, 0), Some(&Color::Grey(1.0)));
+        assert_eq!(grey.get(0, 1), Some(&Color::Grey(0.0)));
+        assert_eq!(
+            String::from_utf8(grey.encode(Format::PlainPgm).unwrap()).unwrap(),
+            "P2\n3 2\n255\n76 255 29 0 128 226\n"
+        );
+        assert_eq!(
+            Color::Primary(PrimaryColor::Green).to_grey(),
+            Some(Color::Grey(0.587))
+        );
+    }
+
+    #[test]
+    fn test_image_palette() {
+        let image = Image::decode(TWO_TONE).unwrap();
+        assert_eq!(image.palette(0), []);
+        // One color is just the average.
+        assert_eq!(image.palette(1), [Color::Rgb(113, 35, 108)]);
+        // Two colors find the two clusters.
+        assert_eq!(
+            image.palette(2),
+            [Color::Rgb(10, 48, 205), Color::Rgb(215, 23, 10)]
+        );
+        // Asking for more colors than there are gives each color once.
+        assert_eq!(image.palette(100).len(), 16);
+        assert_eq!(flag().palette(6).len(), 6);
+    }
+
+    #[test]
+    fn test_image_palette_repeated_color() {
+        // Most pixels are the same red, so the median is red too.  Splitting
+        // there would put red in both boxes, and in the palette twice.
+        let image = Image::decode(MOSTLY_RED).unwrap();
+        assert_eq!(
+            image.palette(2),
+            [Color::Rgb(15, 0, 0), Color::Rgb(200, 0, 0)]
+        );
+        assert_eq!(
+            image.palette(3),
+            [
+                Color::Rgb(5, 0, 0),
+                Color::Rgb(25, 0, 0),
+                Color::Rgb(200, 0, 0)
+            ]
+        );
+        // Each of the 5 colors once, however many are asked for.
+        assert_eq!(image.palette(8).len(), 5);
+    }
+
+    #[test]
+    fn test_image_quantize() {
+        let image = Image::decode(TWO_TONE).unwrap();
+        let quantized = image.quantize(2);
+        let palette = image.palette(2);
+        assert!(quantized
+            .pixels()
+            .iter()
+            .all(|color| palette.contains(color)));
+        // The left half is all the red, and the right half all the blue.
+        let (blue, red) = (palette[0].clone(), palette[1].clone());
+        for row in quantized.rows() {
+            assert_eq!(row, [red.clone(), red.clone(), blue.clone(), blue.clone()]);
+        }
+        // With enough colors, nothing changes.
+        assert_eq!(image.quantize(16), image);
+    }
+}
@@ -0,0 +1,579 @@
+// Parsing a `Color` from text.
+//
+//     #ff8000   #f80   rgb(255, 128, 0)   grey(0.4)   red   rebeccapurple
+//
+// Names and function names are case-insensitive and surrounding whitespace is
+// ignored.  The primaries become `Primary`; any other CSS named color becomes
+// `Other { desc }` with the lower-cased name.  Whatever `describe()` prints
+// for an `Rgb` color parses back too.  Columns in errors are 1-based.
+
+use std::str::FromStr;
+
+use super::{Color, PrimaryColor};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ParseColorError {
+    #[error("empty color")]
+    Empty,
+    #[error("expected {expected}, found {found} at col {col}")]
+    Unexpected {
+        expected: &'static str,
+        found: String,
+        col: usize,
+    },
+    #[error("invalid hex digit `{digit}` at col {col}")]
+    InvalidHexDigit { digit: char, col: usize },
+    #[error("hex colors need 3 or 6 digits, found {len} at col {col}")]
+    HexLength { len: usize, col: usize },
+    #[error("invalid number `{text}` at col {col}")]
+    InvalidNumber { text: String, col: usize },
+    #[error("`{text}` is outside {range} at col {col}")]
+    OutOfRange {
+        text: String,
+        range: &'static str,
+        col: usize,
+    },
+    #[error("unknown color `{name}` at col {col}")]
+    UnknownName { name: String, col: usize },
+}
+
+impl ParseColorError {
+    /// Where the problem is, if the input wasn't empty.
+    pub fn col(&self) -> Option<usize> {
+        match self {
+            ParseColorError::Empty => None,
+            ParseColorError::Unexpected { col, .. }
+            | ParseColorError::InvalidHexDigit { col, .. }
+            | ParseColorError::HexLength { col, .. }
+            | ParseColorError::InvalidNumber { col, .. }
+            | ParseColorError::OutOfRange { col, .. }
+            | ParseColorError::UnknownName { col, .. } => Some(*col),
+        }
+    }
+}
+
+/// Every CSS named color, other than the three that are our primaries.
+const CSS_NAMES: &[&str] = &[
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "rebeccapurple",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+];
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cursor = Cursor::new(s);
+        cursor.skip_whitespace();
+        if cursor.peek().is_none() {
+            return Err(ParseColorError::Empty);
+        }
+        let color = match cursor.peek() {
+            Some('#') => cursor.hex()?,
+            _ => cursor.named()?,
+        };
+        cursor.skip_whitespace();
+        cursor.expect_end()?;
+        Ok(color)
+    }
+}
+
+struct Cursor {
+    chars: Vec<char>,
+    /// Index into `chars`; the column is one more than this.
+    idx: usize,
+}
+
+impl Cursor {
+    fn new(s: &str) -> Self {
+        Cursor {
+            chars: s.chars().collect(),
+            idx: 0,
+        }
+    }
+
+    fn col(&self) -> usize {
+        self.idx + 1
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.idx += 1;
+        }
+    }
+
+    /// Takes characters while `pred` holds, returning them and where they started.
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> (String, usize) {
+        let col = self.col();
+        let start = self.idx;
+        while self.peek().is_some_and(&pred) {
+            self.idx += 1;
+        }
+        (self.chars[start..self.idx].iter().collect(), col)
+    }
+
+    fn unexpected(&self, expected: &'static str) -> ParseColorError {
+        let found = match self.peek() {
+            Some(c) => format!("`{c}`"),
+            None => "end of input".to_owned(),
+        };
+        ParseColorError::Unexpected {
+            expected,
+            found,
+            col: self.col(),
+        }
+    }
+
+    fn expect_char(&mut self, c: char, expected: &'static str) -> Result<(), ParseColorError> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.idx += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    fn expect_word(&mut self, word: &'static str) -> Result<(), ParseColorError> {
+        self.skip_whitespace();
+        let start = self.idx;
+        let (found, col) = self.take_while(|c| c.is_ascii_alphabetic());
+        if found.eq_ignore_ascii_case(word) {
+            Ok(())
+        } else {
+            self.idx = start;
+            let found = match found.is_empty() {
+                true => return Err(self.unexpected(word)),
+                false => format!("`{found}`"),
+            };
+            Err(ParseColorError::Unexpected {
+                expected: word,
+                found,
+                col,
+            })
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), ParseColorError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.unexpected("end of input")),
+        }
+    }
+
+    fn hex(&mut self) -> Result<Color, ParseColorError> {
+        let hash_col = self.col();
+        self.idx += 1;
+        let (digits, col) = self.take_while(|c| c.is_alphanumeric());
+        let values = digits
+            .chars()
+            .enumerate()
+            .map(|(i, digit)| {
+                digit.to_digit(16).map(|value| value as u8).ok_or(
+                    ParseColorError::InvalidHexDigit {
+                        digit,
+                        col: col + i,
+                    },
+                )
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        match values[..] {
+            // #rgb is short for #rrggbb, so each digit is doubled.
+            [r, g, b] => Ok(Color::Rgb(r * 17, g * 17, b * 17)),
+            [r1, r2, g1, g2, b1, b2] => Ok(Color::Rgb(r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2)),
+            _ => Err(ParseColorError::HexLength {
+                len: values.len(),
+                col: hash_col,
+            }),
+        }
+    }
+
+    /// A primary or CSS name, `rgb(...)`, `grey(...)`, or `describe()` output.
+    fn named(&mut self) -> Result<Color, ParseColorError> {
+        let (name, col) = self.take_while(|c| c.is_ascii_alphabetic());
+        if name.is_empty() {
+            return Err(self.unexpected("a color"));
+        }
+        let lower = name.to_ascii_lowercase();
+        self.skip_whitespace();
+        match (lower.as_str(), self.peek()) {
+            ("rgb", Some('(')) => {
+                self.idx += 1;
+                let r = self.channel()?;
+                self.expect_char(',', "`,`")?;
+                let g = self.channel()?;
+                self.expect_char(',', "`,`")?;
+                let b = self.channel()?;
+                self.expect_char(')', "`)`")?;
+                Ok(Color::Rgb(r, g, b))
+            }
+            ("grey" | "gray", Some('(')) => {
+                self.idx += 1;
+                let scale = self.scale()?;
+                self.expect_char(')', "`)`")?;
+                Ok(Color::Grey(scale))
+            }
+            // "RGB Red: 1 Green: 2 Blue: 3", as printed by `describe()`.
+            ("rgb", Some(_)) => {
+                let mut channels = [0; 3];
+                for (channel, word) in channels.iter_mut().zip(["red", "green", "blue"]) {
+                    self.expect_word(word)?;
+                    self.expect_char(':', "`:`")?;
+                    *channel = self.channel()?;
+                }
+                let [r, g, b] = channels;
+                Ok(Color::Rgb(r, g, b))
+            }
+            ("red", _) => Ok(Color::Primary(PrimaryColor::Red)),
+            ("green", _) => Ok(Color::Primary(PrimaryColor::Green)),
+            ("blue", _) => Ok(Color::Primary(PrimaryColor::Blue)),
+            (lower, _) if CSS_NAMES.contains(&lower) => Ok(Color::Other {
+                desc: lower.to_owned(),
+            }),
+            _ => Err(ParseColorError::UnknownName { name, col }),
+        }
+    }
+
+    fn number(&mut self) -> Result<(String, usize), ParseColorError> {
+        self.skip_whitespace();
+        let (text, col) = self.take_while(|c| c.is_ascii_alphanumeric() || c == '.');
+        if text.is_empty() {
+            return Err(self.unexpected("a number"));
+        }
+        Ok((text, col))
+    }
+
+    fn channel(&mut self) -> Result<u8, ParseColorError> {
+        let (text, col) = self.number()?;
+        let value: u32 = text.parse().map_err(|_| ParseColorError::InvalidNumber {
+            text: text.clone(),
+            col,
+        })?;
+        u8::try_from(value).map_err(|_| ParseColorError::OutOfRange {
+            text,
+            range: "0 to 255",
+            col,
+        })
+    }
+
+    fn scale(&mut self) -> Result<f32, ParseColorError> {
+        let (text, col) = self.number()?;
+        let value: f32 = match text.parse() {
+            // `parse` accepts "inf" and "NaN", which we don't want.
+            Ok(value) if text.starts_with(|c: char| c.is_ascii_digit() || c == '.') => value,
+            _ => return Err(ParseColorError::InvalidNumber { text, col }),
+        };
+        if !(0.0..=1.0).contains(&value) {
+            return Err(ParseColorError::OutOfRange {
+                text,
+                range: "0.0 to 1.0",
+                col,
+            });
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<Color, ParseColorError> {
+        s.parse()
+    }
+
+    #[test]
+    fn test_color_parse_forms() {
+        assert_eq!(parse("#ff8000"), Ok(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse("#FF8000"), Ok(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse("#f80"), Ok(Color::Rgb(255, 136, 0)));
+        assert_eq!(parse("rgb(1, 2, 3)"), Ok(Color::Rgb(1, 2, 3)));
+        assert_eq!(parse("  RGB( 1 ,2,3 )  "), Ok(Color::Rgb(1, 2, 3)));
+        assert_eq!(parse("grey(0.4)"), Ok(Color::Grey(0.4)));
+        assert_eq!(parse("gray(1)"), Ok(Color::Grey(1.0)));
+        assert_eq!(parse("Red"), Ok(Color::Primary(PrimaryColor::Red)));
+        assert_eq!(parse("blue"), Ok(Color::Primary(PrimaryColor::Blue)));
+        assert_eq!(
+            parse("RebeccaPurple"),
+            Ok(Color::Other {
+                desc: "rebeccapurple".to_owned()
+            })
+        );
+        // Without parentheses, grey is just another CSS name.
+        assert_eq!(
+            parse("grey"),
+            Ok(Color::Other {
+                desc: "grey".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn test_color_parse_errors() {
+        assert_eq!(parse("   "), Err(ParseColorError::Empty));
+        assert_eq!(
+            parse("#12345g"),
+            Err(ParseColorError::InvalidHexDigit { digit: 'g', col: 7 })
+        );
+        assert_eq!(
+            parse(" #1234"),
+            Err(ParseColorError::HexLength { len: 4, col: 2 })
+        );
+        assert_eq!(
+            parse("rgb(1, 2)"),
+            Err(ParseColorError::Unexpected {
+                expected: "`,`",
+                found: "`)`".to_owned(),
+                col: 9,
+            })
+        );
+        assert_eq!(
+            parse("rgb(1, 256, 3)"),
+            Err(ParseColorError::OutOfRange {
+                text: "256".to_owned(),
+                range: "0 to 255",
+                col: 8,
+            })
+        );
+        assert_eq!(
+            parse("rgb(1, -2, 3)"),
+            Err(ParseColorError::Unexpected {
+                expected: "a number",
+                found: "`-`".to_owned(),
+                col: 8,
+            })
+        );
+        assert_eq!(
+            parse("grey(1.5)"),
+            Err(ParseColorError::OutOfRange {
+                text: "1.5".to_owned(),
+                range: "0.0 to 1.0",
+                col: 6,
+            })
+        );
+        assert_eq!(
+            parse("grey(nan)"),
+            Err(ParseColorError::InvalidNumber {
+                text: "nan".to_owned(),
+                col: 6,
+            })
+        );
+        assert_eq!(
+            parse("octarine"),
+            Err(ParseColorError::UnknownName {
+                name: "octarine".to_owned(),
+                col: 1,
+            })
+        );
+        assert_eq!(
+            parse("red!"),
+            Err(ParseColorError::Unexpected {
+                expected: "end of input",
+                found: "`!`".to_owned(),
+                col: 4,
+            })
+        );
+        assert_eq!(
+            parse("rgb(1, 2, 3").unwrap_err().to_string(),
+            "expected `)`, found end of input at col 12"
+        );
+    }
+
+    #[test]
+    fn test_color_parse_describe_round_trips() {
+        // Not every color, but every value of each channel, against a spread
+        // of the others.
+        let others = [0, 1, 17, 128, 254, 255];
+        for x in 0..=255 {
+            for y in others {
+                for z in others {
+                    for color in [
+                        Color::Rgb(x, y, z),
+                        Color::Rgb(y, x, z),
+                        Color::Rgb(y, z, x),
+                    ] {
+                        assert_eq!(parse(&color.describe()), Ok(color));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_color_parse_describe_errors() {
+        assert_eq!(
+            parse("RGB Red: 1 Green: 2 Bleu: 3"),
+            Err(ParseColorError::Unexpected {
+                expected: "blue",
+                found: "`Bleu`".to_owned(),
+                col: 21,
+            })
+        );
+        assert_eq!(
+            parse("RGB Red: 1 Green: 2"),
+            Err(ParseColorError::Unexpected {
+                expected: "blue",
+                found: "end of input".to_owned(),
+                col: 20,
+            })
+        );
+    }
+
+    #[test]
+    fn test_color_parse_every_css_name() {
+        for name in CSS_NAMES {
+            assert_eq!(
+                parse(&name.to_ascii_uppercase()),
+                Ok(Color::Other {
+                    desc: name.to_string()
+                })
+            );
+        }
+    }
+}
@@ -19,10 +19,31 @@ enum is used.  Similar to the Option-type, there's a variety of syntax to make
 working with them quite ergonomic.
 
 Helpfully, if you join on a thread that panics, the join method returns
-a Result::Err.  Panics can also be handled across FFI boundaries, but that's
-beyond the scope of this module.
+a Result::Err.  Panics can also be handled across FFI boundaries.  Both are
+covered in `panics.rs`.
 */
 
+// A small query language, used by the structured error exercises below.
+mod query;
+
+// The layers below and above it, to show how errors nest: `source()` on an
+// application error leads to a query error, which leads to an I/O error.
+mod app;
+mod storage;
+
+// Acting on the structure of an error: retrying the transient ones.
+mod retry;
+
+// ...and showing the user exactly where they went wrong.
+mod diagnostic;
+
+// Sources of bytes that can be told to fail, for testing I/O error handling.
+mod byte_source;
+
+// Stable codes and long-form explanations for every QueryError, like
+// `rustc --explain E0308`.  Try `cargo run --bin worksheet -- explain Q0001`.
+pub mod catalog;
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -153,42 +174,39 @@ mod tests {
         With map_err and `?`, you can short-curcuit an Err in a function that has a different
         error type.
 
-        Uncomment foo and make it compile with map_err and `?`, and have the assert pass.
+        Uncomment foo and make it compile with map_err and `?`, and have the asserts pass.
         "
         );
 
-        fn read_stuff() -> Result<[u8; 10], std::io::Error> {
-            use std::io::prelude::*;
+        // Reading from a `ByteSource` instead of opening a file directly means
+        // a test can hand in bytes from memory, or a source that fails on cue.
+        use super::byte_source::*;
 
-            let mut f = std::fs::File::open("/dev/random")?;
+        fn read_stuff(source: &mut impl ByteSource) -> Result<[u8; 10], std::io::Error> {
             let mut buffer = [0; 10];
-            // read up to 10 bytes
-            let n = f.read(&mut buffer)?;
+            // read exactly 10 bytes
+            source.fill(&mut buffer)?;
             Ok(buffer)
         }
 
-        // fn foo() -> Result<usize, String> {
-        //     let buffer = read_stuff().????;
+        // fn foo(source: &mut impl ByteSource) -> Result<usize, String> {
+        //     let buffer = read_stuff(source).????;
         //     buffer.len()
         // }
         //
-        // assert_eq!(foo(), Ok(10));
+        // assert_eq!(foo(&mut MemorySource::new(vec![7; 10])), Ok(10));
+        // let mut short = FaultySource::new(MemorySource::new(vec![7; 10]), Fault::Eof { after: 4 });
+        // assert_eq!(foo(&mut short), Err(???));
     }
 
     #[test]
     fn exercise_errors_structured_errors() {
-        enum QueryError {
-            /// May be transient; retry soon.
-            Network { http_code: u16, reason: String },
-            /// User error; don't retry without changing input
-            Parsing { row: u32, col: u32 },
-            /// Non-transient server error; file a bug with oncall
-            Server { desc: String },
-        }
+        // `run_query` runs a small SQL-like language (see `errors/query.rs`)
+        // against a sample `people` table, and reports exactly where a mistake
+        // in the query is.
+        use super::query::*;
 
-        fn run_query(query: &str) -> Result<String, QueryError> {
-            Err(QueryError::Parsing { row: 10, col: 4 })
-        }
+        let result = run_query("SELECT name FROM people WHERE name = 'Bob");
 
         unimplemented!(
             r#"
@@ -199,6 +217,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_errors_structured_diagnostics() {
+        // A `String` error can only be printed.  Because a parse error carries
+        // its position as data, we can point at the exact mistake, like rustc.
+        // Run with `cargo test -- --nocapture` to see it in color.
+        use super::diagnostic::Diagnostic;
+        use super::query::parse;
+
+        let query = "SELECT name FROM people WHERE name = 'Bob";
+        let err = parse(query).unwrap_err();
+        let diagnostic = Diagnostic::from(&err);
+        println!("{}", diagnostic.render_ansi(query));
+
+        let rendered = diagnostic.render(query);
+        let caret_line = format!("  | {}^", " ".repeat(37));
+        assert_eq!(rendered.lines().nth(4), Some(caret_line.as_str()));
+        assert_eq!(rendered.lines().nth(5), Some("  = help: add a closing `'`"));
+    }
+
     #[test]
     fn exercise_errors_thiserror() {
         unimplemented!(
@@ -208,28 +245,47 @@ mod tests {
             It's best to implement this, so that your error is interoperable with other errors.
 
             dtolnay has made a great library called `thiserror`, which does most of the work
-            for you.  Check out https://docs.rs/thiserror/latest/thiserror/ and rewrite
-            QueryError above using `thiserror`.
+            for you.  Check out https://docs.rs/thiserror/latest/thiserror/ and read
+            QueryError in errors/query.rs and StorageError in errors/storage.rs.
+
+            To see what it saves you, implement `std::fmt::Display` and `std::error::Error`
+            by hand for HandRolled below, so that the asserts pass.  Which method of
+            `Error` do you need to write?
             "#
         );
+
+        #[derive(Debug)]
+        enum HandRolled {
+            Empty,
+            BadNumber(std::num::ParseIntError),
+        }
+
+        // impl std::fmt::Display for HandRolled { ??? }
+        // impl std::error::Error for HandRolled { ??? }
+
+        // let err = HandRolled::BadNumber("x".parse::<i32>().unwrap_err());
+        // assert_eq!(err.to_string(), "not a number");
+        // assert_eq!(
+        //     std::error::Error::source(&err).map(|e| e.to_string()),
+        //     Some("invalid digit found in string".to_owned())
+        // );
+        // assert_eq!(HandRolled::Empty.to_string(), "empty input");
+        // assert!(std::error::Error::source(&HandRolled::Empty).is_none());
     }
 
     #[test]
-    fn exercise_errors_anyhow() {
-        unimplemented!(
-            r#"
-            dtolnay has made another error library `anyhow`, for when you just want throw
-            undifferentiated errors.  This is useful in a CLI or executable when you can't
-            reasonable recover from the error.  It has nice features like ease-of-use and
-            ergonomic adding of context.  It also can "absorb" anything that implements
-            std::error::Error (technically implements `From`), so it can easily
-
-            PLEASE NOTE: It is bad practice to use this in libraries
-            that others might consume, because it doesn't give enough information for them
-            to intelligently respond.  It's equivalent to a Java unchecked RuntimeException.
-            Only use it for executables that you control.
-            "#
-        );
+    fn test_errors_anyhow() {
+        // dtolnay has made another error library `anyhow`, for when you just want throw
+        // undifferentiated errors.  This is useful in a CLI or executable when you can't
+        // reasonable recover from the error.  It has nice features like ease-of-use and
+        // ergonomic adding of context.  It also can "absorb" anything that implements
+        // std::error::Error (technically implements `From`), so it can easily wrap the
+        // errors of every library you call.
+        //
+        // PLEASE NOTE: It is bad practice to use this in libraries
+        // that others might consume, because it doesn't give enough information for them
+        // to intelligently respond.  It's equivalent to a Java unchecked RuntimeException.
+        // Only use it for executables that you control.
 
         #[derive(thiserror::Error, Debug)]
         enum InnerError {
@@ -255,9 +311,37 @@ mod tests {
             Ok(i)
         }
 
-        match outer() {
-            Ok(i) => println!("Got {i}"),
-            Err(e) => unimplemented!("Fill this in!"),
+        let err = outer().unwrap_err();
+        assert_eq!(err.to_string(), "Uh-oh, something is WRONG sad");
+        // You can still get the original error back out, if you know its type.
+        assert!(matches!(
+            err.downcast_ref::<InnerError>(),
+            Some(InnerError::Wrong(_))
+        ));
+
+        // The real power is in the chain of `source()`s.  Each layer of a program
+        // wraps the error from the layer below, and anyhow's `context` adds one more
+        // on top.  The report then explains the failure from the top down.
+        use super::app::{report, weekly_report};
+
+        struct BrokenDisk;
+        impl std::io::Read for BrokenDisk {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk on fire"))
+            }
         }
+
+        let err = weekly_report(BrokenDisk).unwrap_err();
+        assert_eq!(
+            report(&err),
+            "\
+error: could not send the weekly report
+caused by:
+    0: report `adults` failed
+    1: could not load a table
+    2: could not read `people.csv`
+    3: disk on fire
+"
+        );
     }
 }
@@ -0,0 +1,122 @@
+// The top layer: an "application" that runs named reports, and reports its
+// own failures with anyhow.
+
+use std::io::Read;
+
+use super::query::{Database, QueryError, Table};
+use super::storage::read_table;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("no report named `{0}`")]
+    UnknownReport(String),
+    #[error("report `{name}` failed")]
+    Report {
+        name: String,
+        #[source]
+        source: QueryError,
+    },
+}
+
+const REPORTS: &[(&str, &str)] = &[
+    (
+        "adults",
+        "SELECT name FROM people WHERE age >= 18 ORDER BY name",
+    ),
+    (
+        "londoners",
+        "SELECT name, age FROM people WHERE city = 'London'",
+    ),
+];
+
+/// The middle layer: load the `people` table and run a query on it.
+/// `StorageError`s become `QueryError::Storage` through `#[from]` and `?`.
+fn query_people(people: impl Read, query: &str) -> Result<Table, QueryError> {
+    let mut db = Database::new();
+    db.insert("people", read_table("people.csv", people)?);
+    db.run_query(query)
+}
+
+pub fn run_report(name: &str, people: impl Read) -> Result<Table, AppError> {
+    let (_, query) = REPORTS
+        .iter()
+        .find(|(report, _)| *report == name)
+        .ok_or_else(|| AppError::UnknownReport(name.to_owned()))?;
+    query_people(people, query).map_err(|source| AppError::Report {
+        name: name.to_owned(),
+        source,
+    })
+}
+
+/// A stand-in for `main`: read `people.csv`, run the weekly report, and add
+/// context for whoever reads the error.
+pub fn weekly_report(people: impl Read) -> anyhow::Result<Table> {
+    use anyhow::Context;
+
+    run_report("adults", people).context("could not send the weekly report")
+}
+
+/// Explain an error and everything that caused it, from the top down.
+pub fn report(err: &anyhow::Error) -> String {
+    let mut out = format!("error: {err}\n");
+    for (i, cause) in err.chain().skip(1).enumerate() {
+        if i == 0 {
+            out += "caused by:\n";
+        }
+        out += &format!("    {i}: {cause}\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PEOPLE: &str = "name,age,city\nBob,17,London\nAlice,34,Paris\n";
+
+    #[test]
+    fn test_app_run_report() {
+        let table = run_report("adults", PEOPLE.as_bytes()).unwrap();
+        assert_eq!(table.to_csv(), "name\nAlice\n");
+        assert!(matches!(
+            run_report("monthly", PEOPLE.as_bytes()),
+            Err(AppError::UnknownReport(name)) if name == "monthly"
+        ));
+    }
+
+    #[test]
+    fn test_app_report_csv_chain() {
+        let err = weekly_report("name,age\nAlice\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            report(&err),
+            "\
+error: could not send the weekly report
+caused by:
+    0: report `adults` failed
+    1: could not load a table
+    2: `people.csv` is not valid CSV
+    3: line 2 has 1 fields, but the header has 2
+"
+        );
+    }
+
+    #[test]
+    fn test_app_report_query_chain() {
+        // The table loads, but has no `city` column for this report.
+        let err =
+            anyhow::Error::from(run_report("londoners", "name,age\n".as_bytes()).unwrap_err());
+        assert_eq!(
+            report(&err),
+            "\
+error: report `londoners` failed
+caused by:
+    0: invalid query at row 1, col 36
+"
+        );
+    }
+
+    #[test]
+    fn test_app_report_single_error() {
+        assert_eq!(report(&anyhow::anyhow!("just me")), "error: just me\n");
+    }
+}
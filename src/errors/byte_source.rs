@@ -0,0 +1,229 @@
+// Where bytes come from, behind a trait so tests can swap in a fake.
+//
+// Code that opens `/dev/random` itself can only be tested on the happy path:
+// there's no way to make the read fail.  Code that takes a `ByteSource` can be
+// handed a `FaultySource`, which fails exactly when and how the test asks.
+
+use std::io::{self, ErrorKind};
+
+pub trait ByteSource {
+    /// Read up to `buf.len()` bytes, returning how many were read.
+    /// `Ok(0)` means the source is exhausted.  Same contract as `io::Read::read`.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Fill `buf` completely, retrying reads that were `Interrupted`.
+    /// Running out of bytes first is an `UnexpectedEof` error.
+    fn fill(&mut self, mut buf: &mut [u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.read(buf) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        format!("source ended with {} bytes still to read", buf.len()),
+                    ))
+                }
+                Ok(n) => buf = &mut buf[n..],
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bytes from a file (or a device, like `/dev/random`).
+pub struct FileSource {
+    file: std::fs::File,
+}
+
+impl FileSource {
+    pub fn open(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        Ok(FileSource {
+            file: std::fs::File::open(path)?,
+        })
+    }
+}
+
+impl ByteSource for FileSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        io::Read::read(&mut self.file, buf)
+    }
+}
+
+/// Bytes from memory.
+pub struct MemorySource {
+    bytes: Vec<u8>,
+    pos: usize,
+}
+
+impl MemorySource {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        MemorySource { bytes, pos: 0 }
+    }
+}
+
+impl ByteSource for MemorySource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let rest = &self.bytes[self.pos..];
+        let n = rest.len().min(buf.len());
+        buf[..n].copy_from_slice(&rest[..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+    /// Fail with an error of this kind, every time, once `after` bytes have been read.
+    Error { after: usize, kind: ErrorKind },
+    /// Return `Interrupted` on the first `times` reads, then behave normally.
+    Interrupted { times: usize },
+    /// Act as if the source ends once `after` bytes have been read.
+    Eof { after: usize },
+}
+
+/// Wraps another source, and fails the way `fault` says.
+pub struct FaultySource<S> {
+    inner: S,
+    fault: Fault,
+    read_so_far: usize,
+}
+
+impl<S: ByteSource> FaultySource<S> {
+    pub fn new(inner: S, fault: Fault) -> Self {
+        FaultySource {
+            inner,
+            fault,
+            read_so_far: 0,
+        }
+    }
+
+    /// Read from `inner`, but no more than `limit` bytes in total.
+    fn read_until(&mut self, limit: usize, buf: &mut [u8]) -> io::Result<usize> {
+        let allowed = limit.saturating_sub(self.read_so_far).min(buf.len());
+        let n = self.inner.read(&mut buf[..allowed])?;
+        self.read_so_far += n;
+        Ok(n)
+    }
+}
+
+impl<S: ByteSource> ByteSource for FaultySource<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.fault {
+            Fault::Error { after, kind } if self.read_so_far >= after => {
+                Err(io::Error::new(kind, "injected fault"))
+            }
+            Fault::Error { after, .. } => self.read_until(after, buf),
+            Fault::Interrupted { times } if times > 0 => {
+                self.fault = Fault::Interrupted { times: times - 1 };
+                Err(ErrorKind::Interrupted.into())
+            }
+            Fault::Interrupted { .. } => self.inner.read(buf),
+            Fault::Eof { after } => self.read_until(after, buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The caller has its own error type, and doesn't want to leak `io::Error`.
+    #[derive(Debug, PartialEq, thiserror::Error)]
+    enum HeaderError {
+        #[error("header is truncated")]
+        Truncated,
+        #[error("could not read header: {0:?}")]
+        Io(ErrorKind),
+        #[error("bad magic number {0:?}")]
+        BadMagic([u8; 2]),
+    }
+
+    fn to_header_error(err: io::Error) -> HeaderError {
+        match err.kind() {
+            ErrorKind::UnexpectedEof => HeaderError::Truncated,
+            kind => HeaderError::Io(kind),
+        }
+    }
+
+    /// Read a 2-byte magic number and a 4-byte big-endian length.
+    fn read_header(source: &mut impl ByteSource) -> Result<u32, HeaderError> {
+        let mut magic = [0; 2];
+        source.fill(&mut magic).map_err(to_header_error)?;
+        if magic != *b"WS" {
+            return Err(HeaderError::BadMagic(magic));
+        }
+        let mut len = [0; 4];
+        source.fill(&mut len).map_err(to_header_error)?;
+        Ok(u32::from_be_bytes(len))
+    }
+
+    fn header_bytes() -> MemorySource {
+        MemorySource::new(vec![b'W', b'S', 0, 0, 1, 2, 99])
+    }
+
+    #[test]
+    fn test_byte_source_memory() {
+        assert_eq!(read_header(&mut header_bytes()), Ok(258));
+        assert_eq!(
+            read_header(&mut MemorySource::new(b"GIF89a".to_vec())),
+            Err(HeaderError::BadMagic(*b"GI"))
+        );
+    }
+
+    #[test]
+    fn test_byte_source_error_after_n_bytes() {
+        // Fails partway through the length, after the magic number was fine.
+        let fault = Fault::Error {
+            after: 3,
+            kind: ErrorKind::ConnectionReset,
+        };
+        let mut source = FaultySource::new(header_bytes(), fault);
+        assert_eq!(
+            read_header(&mut source),
+            Err(HeaderError::Io(ErrorKind::ConnectionReset))
+        );
+        // Exactly 3 bytes got through.
+        assert_eq!(source.read_so_far, 3);
+    }
+
+    #[test]
+    fn test_byte_source_interrupted_is_retried() {
+        let mut source = FaultySource::new(header_bytes(), Fault::Interrupted { times: 3 });
+        assert_eq!(read_header(&mut source), Ok(258));
+
+        // A bare `read` still sees the interruption; only `fill` retries.
+        let mut source = FaultySource::new(header_bytes(), Fault::Interrupted { times: 1 });
+        let err = source.read(&mut [0; 6]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Interrupted);
+        assert_eq!(read_header(&mut source), Ok(258));
+    }
+
+    #[test]
+    fn test_byte_source_eof() {
+        let mut source = FaultySource::new(header_bytes(), Fault::Eof { after: 4 });
+        assert_eq!(read_header(&mut source), Err(HeaderError::Truncated));
+
+        let mut empty = MemorySource::new(vec![]);
+        assert_eq!(read_header(&mut empty), Err(HeaderError::Truncated));
+    }
+
+    #[test]
+    fn test_byte_source_file() {
+        let path = std::env::temp_dir().join(format!("byte_source_{}.bin", std::process::id()));
+        std::fs::write(&path, [b'W', b'S', 0, 0, 0, 7]).unwrap();
+        let mut source = FileSource::open(&path).unwrap();
+        assert_eq!(read_header(&mut source), Ok(7));
+        std::fs::remove_file(&path).unwrap();
+
+        // Opening fails before there's a source at all; `?` works there too.
+        fn open_and_read(path: &std::path::Path) -> Result<u32, HeaderError> {
+            let mut source = FileSource::open(path).map_err(to_header_error)?;
+            read_header(&mut source)
+        }
+        assert_eq!(
+            open_and_read(&path),
+            Err(HeaderError::Io(ErrorKind::NotFound))
+        );
+    }
+}
@@ -0,0 +1,180 @@
+// Every `QueryError` variant has a stable code, so users can search for it,
+// and so support can tell which failure a user hit even if the message
+// wording changes.  Codes are never reused or renumbered: when a variant is
+// removed, its code is retired along with it.
+
+use super::query::QueryError;
+
+pub struct Explanation {
+    pub code: &'static str,
+    pub title: &'static str,
+    pub text: &'static str,
+}
+
+static PARSING: Explanation = Explanation {
+    code: "Q0001",
+    title: "the query is invalid",
+    text: "\
+The query text couldn't be parsed, or it names a table or column that doesn't
+exist, or it compares values of different types.  The error includes the row
+and column of the mistake.
+
+    SELECT name FROM people WHERE age > 'old'
+                                      ^ cannot compare number with string
+
+Fix the query before retrying; the same query will always fail the same way.",
+};
+
+static NETWORK: Explanation = Explanation {
+    code: "Q0002",
+    title: "a network request failed",
+    text: "\
+The query couldn't reach the server, or the server didn't answer in time.  The
+error includes the HTTP status code and reason.
+
+These failures are often transient.  Retry after a short, randomized delay
+(see `RetryPolicy`), and only report a problem if retries keep failing.",
+};
+
+static SERVER: Explanation = Explanation {
+    code: "Q0003",
+    title: "the server hit an internal error",
+    text: "\
+The server found that one of its own invariants was broken: something that
+should be impossible happened.  This is not caused by the query.
+
+Don't retry.  File a bug with oncall, including the error's description and
+the query that triggered it.",
+};
+
+static STORAGE: Explanation = Explanation {
+    code: "Q0004",
+    title: "a table couldn't be loaded",
+    text: "\
+A table needed by the query couldn't be read from storage, or wasn't valid
+CSV.  The underlying I/O or CSV error is the error's `source()`, and names
+the file and line.
+
+Check that the file exists, is readable, and that every row has one field
+per header column.  A read that timed out may work if retried.",
+};
+
+/// Every explanation, in code order.
+pub static CATALOG: &[&Explanation] = &[&PARSING, &NETWORK, &SERVER, &STORAGE];
+
+impl QueryError {
+    /// The long-form explanation for this variant.  The match is exhaustive,
+    /// so adding a variant without an explanation won't compile.
+    pub fn explanation(&self) -> &'static Explanation {
+        match self {
+            QueryError::Parsing { .. } => &PARSING,
+            QueryError::Network { .. } => &NETWORK,
+            QueryError::Server { .. } => &SERVER,
+            QueryError::Storage(_) => &STORAGE,
+        }
+    }
+
+    /// This variant's stable code.
+    pub fn code(&self) -> &'static str {
+        self.explanation().code
+    }
+}
+
+/// Look up a code, ignoring case: `q0001` finds `Q0001`.
+pub fn explain(code: &str) -> Option<&'static Explanation> {
+    CATALOG
+        .iter()
+        .copied()
+        .find(|entry| entry.code.eq_ignore_ascii_case(code))
+}
+
+/// The output of `worksheet explain <code>`, or the error message if the
+/// code isn't known.
+pub fn explain_command(code: &str) -> Result<String, String> {
+    match explain(code) {
+        Some(entry) => Ok(format!(
+            "{}: {}\n\n{}\n",
+            entry.code, entry.title, entry.text
+        )),
+        None => Err(format!("no error code `{code}` (codes look like Q0001)")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One error of every variant.  The `match` stops compiling when a variant
+    /// is added, as a reminder to add an example here too.
+    fn one_of_each() -> Vec<QueryError> {
+        let examples = vec![
+            QueryError::Parsing { row: 1, col: 1 },
+            QueryError::Network {
+                http_code: 503,
+                reason: "Service Unavailable".to_owned(),
+            },
+            QueryError::Server {
+                desc: "oops".to_owned(),
+            },
+            QueryError::Storage(
+                super::super::storage::read_table("t.csv", "".as_bytes()).unwrap_err(),
+            ),
+        ];
+        for example in &examples {
+            match example {
+                QueryError::Parsing { .. }
+                | QueryError::Network { .. }
+                | QueryError::Server { .. }
+                | QueryError::Storage(_) => {}
+            }
+        }
+        examples
+    }
+
+    #[test]
+    fn test_catalog_explains_every_variant() {
+        for err in one_of_each() {
+            // `explanation()` can't be missing, but it could be left out of
+            // CATALOG, and then `worksheet explain` wouldn't find it.
+            let code = err.code();
+            assert!(
+                explain(code).is_some_and(|entry| std::ptr::eq(entry, err.explanation())),
+                "{code} ({err:?}) isn't in CATALOG"
+            );
+        }
+    }
+
+    #[test]
+    fn test_catalog_codes_are_unique() {
+        let mut codes: Vec<_> = one_of_each().iter().map(QueryError::code).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), one_of_each().len());
+
+        let mut catalog_codes: Vec<_> = CATALOG.iter().map(|entry| entry.code).collect();
+        catalog_codes.sort();
+        catalog_codes.dedup();
+        assert_eq!(catalog_codes.len(), CATALOG.len());
+    }
+
+    #[test]
+    fn test_catalog_codes_are_stable() {
+        // These must never change; people have them in bug reports and runbooks.
+        let codes: Vec<_> = one_of_each().iter().map(QueryError::code).collect();
+        assert_eq!(codes, vec!["Q0001", "Q0002", "Q0003", "Q0004"]);
+        assert_eq!(
+            QueryError::Parsing { row: 3, col: 4 }.explanation().title,
+            "the query is invalid"
+        );
+    }
+
+    #[test]
+    fn test_catalog_explain_command() {
+        let text = explain_command("q0002").unwrap();
+        assert!(text.starts_with("Q0002: a network request failed\n\nThe query couldn't"));
+        assert_eq!(
+            explain_command("E0308"),
+            Err("no error code `E0308` (codes look like Q0001)".to_owned())
+        );
+    }
+}
@@ -0,0 +1,251 @@
+// Rendering query errors the way rustc renders compile errors:
+//
+// error: expected a table name, found end of query
+//  --> query:1:14
+//   |
+// 1 | SELECT a FROM
+//   |              ^
+//
+// None of this is possible with a `String` error.  A string can say what went
+// wrong, but to point at the mistake we need the row and column as data.
+
+use super::query::{ParseError, ParseErrorKind, QueryError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// 1-based, like `QueryError::Parsing`.
+    pub row: u32,
+    pub col: u32,
+    pub help: Option<String>,
+}
+
+/// The escape codes to wrap each part of a diagnostic in.
+struct Style {
+    error: &'static str,
+    message: &'static str,
+    gutter: &'static str,
+    help: &'static str,
+    reset: &'static str,
+}
+
+const PLAIN: Style = Style {
+    error: "",
+    message: "",
+    gutter: "",
+    help: "",
+    reset: "",
+};
+
+const ANSI: Style = Style {
+    error: "\x1b[1;31m",
+    message: "\x1b[1m",
+    gutter: "\x1b[1;34m",
+    help: "\x1b[1;36m",
+    reset: "\x1b[0m",
+};
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, row: u32, col: u32) -> Self {
+        Diagnostic {
+            message: message.into(),
+            row,
+            col,
+            help: None,
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// A diagnostic for a `QueryError::Parsing`.  The error only knows the
+    /// position, so the message is generic; prefer `From<&ParseError>`.
+    pub fn from_query_error(err: &QueryError) -> Option<Self> {
+        match err {
+            QueryError::Parsing { row, col } => Some(Diagnostic::new("invalid query", *row, *col)),
+            QueryError::Network { .. } | QueryError::Server { .. } | QueryError::Storage(_) => None,
+        }
+    }
+
+    /// Render without color, e.g. for logs.
+    pub fn render(&self, source: &str) -> String {
+        self.render_with(source, &PLAIN)
+    }
+
+    /// Render with ANSI colors, for a terminal.
+    pub fn render_ansi(&self, source: &str) -> String {
+        self.render_with(source, &ANSI)
+    }
+
+    fn render_with(&self, source: &str, style: &Style) -> String {
+        let Style {
+            error,
+            message,
+            gutter,
+            help,
+            reset,
+        } = style;
+        let row = self.row.to_string();
+        let pad = " ".repeat(row.len());
+        // An error at the very end of the input may be on a line past the last.
+        let line = source
+            .lines()
+            .nth(self.row.saturating_sub(1) as usize)
+            .unwrap_or("");
+        // Copy tabs from the line, so the caret lines up however they're shown.
+        let indent: String = line
+            .chars()
+            .chain(std::iter::repeat(' '))
+            .take(self.col.saturating_sub(1) as usize)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let mut out = format!("{error}error{reset}{message}: {}{reset}\n", self.message);
+        out += &format!("{pad}{gutter}-->{reset} query:{}:{}\n", self.row, self.col);
+        out += &format!("{pad} {gutter}|{reset}\n");
+        out += &format!("{gutter}{row} |{reset} {line}\n");
+        out += &format!("{pad} {gutter}|{reset} {indent}{error}^{reset}\n");
+        if let Some(note) = &self.help {
+            out += &format!("{pad} {gutter}={reset} {help}help{reset}: {note}\n");
+        }
+        out
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(err: &ParseError) -> Self {
+        let diagnostic = Diagnostic::new(err.kind.to_string(), err.pos.row, err.pos.col);
+        match help(&err.kind) {
+            Some(note) => diagnostic.with_help(note),
+            None => diagnostic,
+        }
+    }
+}
+
+fn help(kind: &ParseErrorKind) -> Option<&'static str> {
+    let note = match kind {
+        ParseErrorKind::UnexpectedChar(';') => "queries don't end with `;`; remove it",
+        ParseErrorKind::UnexpectedChar('"') => "strings use single quotes, like 'this'",
+        ParseErrorKind::UnexpectedChar('!') => "did you mean `!=`?",
+        ParseErrorKind::UnterminatedString => "add a closing `'`",
+        ParseErrorKind::InvalidNumber(_) => "numbers look like `42` or `-1.5`",
+        ParseErrorKind::TypeMismatch { .. } => {
+            "compare numbers with numbers, and strings with strings"
+        }
+        _ => return None,
+    };
+    Some(note)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::query::{parse, run_query};
+    use super::*;
+
+    fn render(query: &str) -> String {
+        Diagnostic::from(&parse(query).unwrap_err()).render(query)
+    }
+
+    #[test]
+    fn test_diagnostic_render() {
+        assert_eq!(
+            render("SELECT a FROM"),
+            "\
+error: expected a table name, found end of query
+ --> query:1:14
+  |
+1 | SELECT a FROM
+  |              ^
+"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_render_with_help() {
+        assert_eq!(
+            render("SELECT name\nFROM people\nWHERE name = 'Bob"),
+            "\
+error: unterminated string
+ --> query:3:14
+  |
+3 | WHERE name = 'Bob
+  |              ^
+  = help: add a closing `'`
+"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_render_wide_gutter() {
+        let query = format!("{}SELECT * FROM t;", "\n".repeat(11));
+        assert_eq!(
+            render(&query),
+            "\
+error: unexpected character `;`
+  --> query:12:16
+   |
+12 | SELECT * FROM t;
+   |                ^
+   = help: queries don't end with `;`; remove it
+"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_render_keeps_tabs() {
+        assert_eq!(
+            render("SELECT *\n\tFROM\t\"t\""),
+            "\
+error: unexpected character `\"`
+ --> query:2:7
+  |
+2 | \tFROM\t\"t\"
+  | \t    \t^
+  = help: strings use single quotes, like 'this'
+"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_render_past_last_line() {
+        assert_eq!(
+            render("SELECT a FROM t WHERE (a = 1\n"),
+            "\
+error: expected `)`, found end of query
+ --> query:2:1
+  |
+2 | 
+  | ^
+"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_render_ansi() {
+        let diagnostic = Diagnostic::new("expected `)`", 1, 3).with_help("close it");
+        assert_eq!(
+            diagnostic.render_ansi("(a"),
+            "\x1b[1;31merror\x1b[0m\x1b[1m: expected `)`\x1b[0m\n\
+             \x20\x1b[1;34m-->\x1b[0m query:1:3\n\
+             \x20\x20\x1b[1;34m|\x1b[0m\n\
+             \x1b[1;34m1 |\x1b[0m (a\n\
+             \x20\x20\x1b[1;34m|\x1b[0m   \x1b[1;31m^\x1b[0m\n\
+             \x20\x20\x1b[1;34m=\x1b[0m \x1b[1;36mhelp\x1b[0m: close it\n"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_from_query_error() {
+        let err = run_query("SELECT name FROM persons").unwrap_err();
+        let diagnostic = Diagnostic::from_query_error(&err).unwrap();
+        assert_eq!(diagnostic, Diagnostic::new("invalid query", 1, 18));
+        assert_eq!(
+            Diagnostic::from_query_error(&QueryError::Server {
+                desc: "oops".to_owned()
+            }),
+            None
+        );
+    }
+}
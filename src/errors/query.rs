@@ -0,0 +1,419 @@
+// A mini query language, so `QueryError` has something real to report.
+//
+// SELECT name, age FROM people WHERE age >= 18 AND NOT name = 'Bob'
+//     ORDER BY age DESC, name LIMIT 10
+//
+// Keywords are case-insensitive, strings are single-quoted (with '' as an
+// escaped quote), and rows and columns in errors are 1-based.  Queries run
+// against in-memory tables loaded from CSV.
+
+mod exec;
+mod lexer;
+mod parser;
+mod table;
+
+pub use exec::Database;
+pub use parser::parse;
+pub use table::{CsvError, Table};
+
+use super::storage::StorageError;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum QueryError {
+    /// May be transient; retry soon.
+    #[error("network error {http_code}: {reason}")]
+    Network { http_code: u16, reason: String },
+    /// User error; don't retry without changing input
+    #[error("invalid query at row {row}, col {col}")]
+    Parsing { row: u32, col: u32 },
+    /// Non-transient server error; file a bug with oncall
+    #[error("server error: {desc}")]
+    Server { desc: String },
+    /// A table couldn't be loaded; the cause is in `source()`.  Transient if
+    /// the read timed out.
+    #[error("could not load a table")]
+    Storage(#[from] StorageError),
+}
+
+impl QueryError {
+    /// Whether trying the same query again might succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            QueryError::Network { .. } => true,
+            QueryError::Storage(err) => err.is_transient(),
+            QueryError::Parsing { .. } | QueryError::Server { .. } => false,
+        }
+    }
+}
+
+/// A 1-based position in the query text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pos {
+    pub row: u32,
+    pub col: u32,
+}
+
+/// What was wrong with the query text, and where.  Most of these are found
+/// while parsing, but unknown names and mismatched types are only found
+/// once the query is checked against a table.  This is more detailed than
+/// `QueryError::Parsing`, which only keeps the position.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{kind} at row {}, col {}", pos.row, pos.col)]
+pub struct ParseError {
+    pub pos: Pos,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ParseErrorKind {
+    #[error("unexpected character `{0}`")]
+    UnexpectedChar(char),
+    #[error("unterminated string")]
+    UnterminatedString,
+    #[error("invalid number `{0}`")]
+    InvalidNumber(String),
+    #[error("expected {expected}, found {found}")]
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+    },
+    #[error("expected {expected}, found end of query")]
+    UnexpectedEnd { expected: &'static str },
+    #[error("no table named `{0}`")]
+    UnknownTable(String),
+    #[error("no column named `{0}`")]
+    UnknownColumn(String),
+    #[error("cannot compare {left} with {right}")]
+    TypeMismatch {
+        left: &'static str,
+        right: &'static str,
+    },
+}
+
+impl From<ParseError> for QueryError {
+    fn from(err: ParseError) -> Self {
+        QueryError::Parsing {
+            row: err.pos.row,
+            col: err.pos.col,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub columns: Columns,
+    pub table: Ident,
+    pub filter: Option<Expr>,
+    pub order_by: Vec<OrderBy>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub column: Ident,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Columns {
+    All,
+    Named(Vec<Ident>),
+}
+
+/// A table or column name, with where it appeared in the query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ident {
+    pub name: String,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Compare {
+        left: Operand,
+        op: CmpOp,
+        right: Operand,
+        /// Where the operator is, for reporting type mismatches.
+        pos: Pos,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    Column(Ident),
+    Number(f64),
+    Str(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl std::fmt::Display for CmpOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            CmpOp::Eq => "=",
+            CmpOp::Ne => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+        };
+        f.write_str(op)
+    }
+}
+
+impl std::fmt::Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Column(ident) => write!(f, "{}", ident.name),
+            Operand::Number(n) => write!(f, "{n}"),
+            Operand::Str(s) => write!(f, "'{}'", s.replace('\'', "''")),
+        }
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Compare {
+                left, op, right, ..
+            } => write!(f, "{left} {op} {right}"),
+            Expr::And(left, right) => write!(f, "({left} AND {right})"),
+            Expr::Or(left, right) => write!(f, "({left} OR {right})"),
+            Expr::Not(inner) => write!(f, "NOT {inner}"),
+        }
+    }
+}
+
+/// Prints the query in a normalized form: upper-case keywords, single spaces,
+/// and explicit parentheses around every AND and OR.
+impl std::fmt::Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SELECT ")?;
+        match &self.columns {
+            Columns::All => f.write_str("*")?,
+            Columns::Named(names) => {
+                let names: Vec<&str> = names.iter().map(|ident| ident.name.as_str()).collect();
+                f.write_str(&names.join(", "))?;
+            }
+        }
+        write!(f, " FROM {}", self.table.name)?;
+        if let Some(filter) = &self.filter {
+            write!(f, " WHERE {filter}")?;
+        }
+        let order_by: Vec<String> = self
+            .order_by
+            .iter()
+            .map(|order| match order.descending {
+                true => format!("{} DESC", order.column.name),
+                false => order.column.name.clone(),
+            })
+            .collect();
+        if !order_by.is_empty() {
+            write!(f, " ORDER BY {}", order_by.join(", "))?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {limit}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse and run a query against `Database::sample()`.
+pub fn run_query(query: &str) -> Result<Table, QueryError> {
+    Database::sample().run_query(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(query: &str) -> String {
+        parse(query).unwrap().to_string()
+    }
+
+    fn parse_err(query: &str) -> (u32, u32, ParseErrorKind) {
+        let err = parse(query).unwrap_err();
+        (err.pos.row, err.pos.col, err.kind)
+    }
+
+    #[test]
+    fn test_query_parse_normalizes() {
+        assert_eq!(
+            normalize("select name,age from people where age >= 18 and not name = 'Bob'"),
+            "SELECT name, age FROM people WHERE (age >= 18 AND NOT name = 'Bob')"
+        );
+        assert_eq!(normalize("SELECT * FROM t"), "SELECT * FROM t");
+        assert_eq!(
+            normalize("SELECT * FROM t order by a desc, b asc, c limit 3"),
+            "SELECT * FROM t ORDER BY a DESC, b, c LIMIT 3"
+        );
+    }
+
+    #[test]
+    fn test_query_parse_precedence() {
+        // AND binds tighter than OR, and parentheses override it.
+        assert_eq!(
+            normalize("SELECT * FROM t WHERE a = 1 OR b = 2 AND c = 3"),
+            "SELECT * FROM t WHERE (a = 1 OR (b = 2 AND c = 3))"
+        );
+        assert_eq!(
+            normalize("SELECT * FROM t WHERE (a = 1 OR b = 2) AND c = 3"),
+            "SELECT * FROM t WHERE ((a = 1 OR b = 2) AND c = 3)"
+        );
+    }
+
+    #[test]
+    fn test_query_parse_keeps_positions() {
+        let query = parse("SELECT name\nFROM   people").unwrap();
+        assert_eq!(query.table.pos, Pos { row: 2, col: 8 });
+        assert_eq!(
+            query.columns,
+            Columns::Named(vec![Ident {
+                name: "name".to_owned(),
+                pos: Pos { row: 1, col: 8 },
+            }])
+        );
+    }
+
+    #[test]
+    fn test_query_error_unexpected_char() {
+        assert_eq!(
+            parse_err("SELECT a FROM t WHERE a = 1;"),
+            (1, 28, ParseErrorKind::UnexpectedChar(';'))
+        );
+        assert_eq!(
+            parse_err("SELECT a FROM t\nWHERE a ! 1"),
+            (2, 9, ParseErrorKind::UnexpectedChar('!'))
+        );
+    }
+
+    #[test]
+    fn test_query_error_unterminated_string() {
+        // Reported where the string started, since that's where the fix goes.
+        assert_eq!(
+            parse_err("SELECT a FROM t\nWHERE name = 'Bob"),
+            (2, 14, ParseErrorKind::UnterminatedString)
+        );
+    }
+
+    #[test]
+    fn test_query_error_invalid_number() {
+        assert_eq!(
+            parse_err("SELECT a FROM t WHERE a > 1.2.3"),
+            (1, 27, ParseErrorKind::InvalidNumber("1.2.3".to_owned()))
+        );
+        assert_eq!(
+            parse_err("SELECT a FROM t WHERE a > 12abc"),
+            (1, 27, ParseErrorKind::InvalidNumber("12abc".to_owned()))
+        );
+        // Rust would parse these as floats, but they aren't numbers here.
+        for bad in ["-inf", "-nan", "-infinity", "-"] {
+            assert_eq!(
+                parse_err(&format!("SELECT a FROM t WHERE a > {bad}")),
+                (1, 27, ParseErrorKind::InvalidNumber(bad.to_owned()))
+            );
+        }
+        // Exponents may have a sign.
+        assert!(parse("SELECT a FROM t WHERE a > 1e-5 AND a < -2.5E+3").is_ok());
+    }
+
+    #[test]
+    fn test_query_error_unexpected_token() {
+        assert_eq!(
+            parse_err("SELCT a FROM t"),
+            (
+                1,
+                1,
+                ParseErrorKind::UnexpectedToken {
+                    expected: "SELECT",
+                    found: "identifier `SELCT`".to_owned(),
+                }
+            )
+        );
+        assert_eq!(
+            parse_err("SELECT a b FROM t"),
+            (
+                1,
+                10,
+                ParseErrorKind::UnexpectedToken {
+                    expected: "FROM or `,`",
+                    found: "identifier `b`".to_owned(),
+                }
+            )
+        );
+        assert_eq!(
+            parse_err("SELECT a FROM t\n  WHERE a = 1 b"),
+            (
+                2,
+                15,
+                ParseErrorKind::UnexpectedToken {
+                    expected: "end of query",
+                    found: "identifier `b`".to_owned(),
+                }
+            )
+        );
+        assert_eq!(
+            parse_err("SELECT a FROM t LIMIT 2.5"),
+            (
+                1,
+                23,
+                ParseErrorKind::UnexpectedToken {
+                    expected: "a non-negative whole number",
+                    found: "number 2.5".to_owned(),
+                }
+            )
+        );
+        assert_eq!(
+            parse_err("SELECT a FROM t WHERE a 1"),
+            (
+                1,
+                25,
+                ParseErrorKind::UnexpectedToken {
+                    expected: "a comparison operator",
+                    found: "number 1".to_owned(),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_query_error_unexpected_end() {
+        assert_eq!(
+            parse_err("SELECT a FROM"),
+            (
+                1,
+                14,
+                ParseErrorKind::UnexpectedEnd {
+                    expected: "a table name"
+                }
+            )
+        );
+        assert_eq!(
+            parse_err("SELECT a FROM t WHERE (a = 1\n"),
+            (2, 1, ParseErrorKind::UnexpectedEnd { expected: "`)`" })
+        );
+    }
+
+    #[test]
+    fn test_query_error_converts_to_parsing() {
+        assert_eq!(
+            run_query("SELECT a FROM t\nWHERE name = 'Bob"),
+            Err(QueryError::Parsing { row: 2, col: 14 })
+        );
+        assert_eq!(
+            parse("SELECT a FROM").unwrap_err().to_string(),
+            "expected a table name, found end of query at row 1, col 14"
+        );
+    }
+}
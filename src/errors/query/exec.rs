@@ -0,0 +1,407 @@
+// Runs parsed queries against in-memory tables.
+//
+// Mistakes in the query (unknown names, comparing a number with a string)
+// are the user's to fix, so they're reported as `QueryError::Parsing` at the
+// offending spot.  They're all found before looking at any rows, using the
+// column types worked out when the table was loaded, so whether a query is
+// valid never depends on which rows it happens to compare.
+//
+// `QueryError::Server` is only for broken internal invariants: things that
+// should be impossible, but shouldn't take the whole process down if they
+// happen.
+
+use std::collections::HashMap;
+
+use super::table::{CsvError, Table, Value};
+use super::{
+    parse, CmpOp, Columns, Expr, Ident, Operand, ParseError, ParseErrorKind, Query, QueryError,
+};
+
+const SAMPLE_PEOPLE: &str = "\
+name,age,city
+Alice,34,Paris
+Bob,17,London
+Carol,52,Tokyo
+Dan,25,London
+";
+
+#[derive(Debug, Clone, Default)]
+pub struct Database {
+    tables: HashMap<String, Table>,
+}
+
+/// An `Expr` with its column names resolved to indices.
+enum Bound {
+    Compare {
+        left: BoundOperand,
+        op: CmpOp,
+        right: BoundOperand,
+    },
+    And(Box<Bound>, Box<Bound>),
+    Or(Box<Bound>, Box<Bound>),
+    Not(Box<Bound>),
+}
+
+enum BoundOperand {
+    Column(usize),
+    Value(Value),
+}
+
+impl BoundOperand {
+    /// `None` for a column of a table with no rows.
+    fn type_name(&self, table: &Table) -> Option<&'static str> {
+        match self {
+            BoundOperand::Column(i) => table.column_type(*i),
+            BoundOperand::Value(value) => Some(value.type_name()),
+        }
+    }
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A database with a small `people` table, for examples and exercises.
+    pub fn sample() -> Self {
+        let mut db = Database::new();
+        db.load_csv("people", SAMPLE_PEOPLE)
+            .expect("the sample CSV is well-formed");
+        db
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, table: Table) {
+        self.tables.insert(name.into(), table);
+    }
+
+    pub fn load_csv(&mut self, name: impl Into<String>, csv: &str) -> Result<(), CsvError> {
+        self.insert(name, Table::from_csv(csv)?);
+        Ok(())
+    }
+
+    pub fn run_query(&self, query: &str) -> Result<Table, QueryError> {
+        let query = parse(query)?;
+        self.execute(&query)
+    }
+
+    pub fn execute(&self, query: &Query) -> Result<Table, QueryError> {
+        let table = self
+            .tables
+            .get(&query.table.name)
+            .ok_or_else(|| ParseError {
+                pos: query.table.pos,
+                kind: ParseErrorKind::UnknownTable(query.table.name.clone()),
+            })?;
+
+        // Resolve every name up front, so mistakes are caught even if the
+        // table is empty.
+        let projection = match &query.columns {
+            Columns::All => (0..table.columns.len()).collect(),
+            Columns::Named(names) => names
+                .iter()
+                .map(|name| column_index(table, name))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        let filter = query
+            .filter
+            .as_ref()
+            .map(|expr| bind(table, expr))
+            .transpose()?;
+        let order_by = query
+            .order_by
+            .iter()
+            .map(|order| Ok((column_index(table, &order.column)?, order.descending)))
+            .collect::<Result<Vec<_>, ParseError>>()?;
+
+        let mut rows = Vec::new();
+        for (i, row) in table.rows.iter().enumerate() {
+            let keep = match &filter {
+                Some(filter) => eval(filter, row, i)?,
+                None => true,
+            };
+            if keep {
+                rows.push(row);
+            }
+        }
+
+        // Every index below was resolved against `table`, whose rows all have
+        // one cell per column.  Check anyway, rather than panicking.
+        for row in &rows {
+            if row.len() != table.columns.len() {
+                return Err(QueryError::Server {
+                    desc: format!(
+                        "row has {} cells but table `{}` has {} columns",
+                        row.len(),
+                        query.table.name,
+                        table.columns.len()
+                    ),
+                });
+            }
+        }
+
+        // `sort_by` is stable, so rows that tie keep their original order.
+        rows.sort_by(|a, b| {
+            order_by
+                .iter()
+                .map(|&(i, descending)| {
+                    let ordering = a[i].sort_cmp(&b[i]);
+                    match descending {
+                        true => ordering.reverse(),
+                        false => ordering,
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        if let Some(limit) = query.limit {
+            rows.truncate(limit);
+        }
+
+        let columns = projection
+            .iter()
+            .map(|&i| table.columns[i].clone())
+            .collect();
+        let rows = rows
+            .into_iter()
+            .map(|row| projection.iter().map(|&i| row[i].clone()).collect())
+            .collect();
+        Ok(Table { columns, rows })
+    }
+}
+
+fn column_index(table: &Table, name: &Ident) -> Result<usize, ParseError> {
+    table.column_index(&name.name).ok_or_else(|| ParseError {
+        pos: name.pos,
+        kind: ParseErrorKind::UnknownColumn(name.name.clone()),
+    })
+}
+
+fn bind(table: &Table, expr: &Expr) -> Result<Bound, ParseError> {
+    let bind_operand = |operand: &Operand| -> Result<BoundOperand, ParseError> {
+        Ok(match operand {
+            Operand::Column(name) => BoundOperand::Column(column_index(table, name)?),
+            Operand::Number(n) => BoundOperand::Value(Value::Number(*n)),
+            Operand::Str(s) => BoundOperand::Value(Value::Str(s.clone())),
+        })
+    };
+    Ok(match expr {
+        Expr::Compare {
+            left,
+            op,
+            right,
+            pos,
+        } => {
+            let (left, right) = (bind_operand(left)?, bind_operand(right)?);
+            if let (Some(left), Some(right)) = (left.type_name(table), right.type_name(table)) {
+                if left != right {
+                    return Err(ParseError {
+                        pos: *pos,
+                        kind: ParseErrorKind::TypeMismatch { left, right },
+                    });
+                }
+            }
+            Bound::Compare {
+                left,
+                op: *op,
+                right,
+            }
+        }
+        Expr::And(left, right) => {
+            Bound::And(Box::new(bind(table, left)?), Box::new(bind(table, right)?))
+        }
+        Expr::Or(left, right) => {
+            Bound::Or(Box::new(bind(table, left)?), Box::new(bind(table, right)?))
+        }
+        Expr::Not(inner) => Bound::Not(Box::new(bind(table, inner)?)),
+    })
+}
+
+/// `index` is the row's position in the table, for error messages.
+fn eval(expr: &Bound, row: &[Value], index: usize) -> Result<bool, QueryError> {
+    let value = |operand: &BoundOperand| -> Result<Value, QueryError> {
+        match operand {
+            BoundOperand::Column(i) => row.get(*i).cloned().ok_or_else(|| QueryError::Server {
+                desc: format!(
+                    "column index {i} is out of range for a row of {}",
+                    row.len()
+                ),
+            }),
+            BoundOperand::Value(value) => Ok(value.clone()),
+        }
+    };
+    Ok(match expr {
+        Bound::Compare { left, op, right } => {
+            let (left, right) = (value(left)?, value(right)?);
+            // `bind` checked the types, and every column has just one.
+            let ordering = left.compare(&right).ok_or_else(|| QueryError::Server {
+                desc: format!(
+                    "row {index}: cannot compare {} with {}",
+                    left.type_name(),
+                    right.type_name()
+                ),
+            })?;
+            match op {
+                CmpOp::Eq => ordering.is_eq(),
+                CmpOp::Ne => ordering.is_ne(),
+                CmpOp::Lt => ordering.is_lt(),
+                CmpOp::Le => ordering.is_le(),
+                CmpOp::Gt => ordering.is_gt(),
+                CmpOp::Ge => ordering.is_ge(),
+            }
+        }
+        // `&&` and `||` short-circuit, so the right side may never be checked.
+        Bound::And(left, right) => eval(left, row, index)? && eval(right, row, index)?,
+        Bound::Or(left, right) => eval(left, row, index)? || eval(right, row, index)?,
+        Bound::Not(inner) => !eval(inner, row, index)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv(query: &str) -> String {
+        Database::sample().run_query(query).unwrap().to_csv()
+    }
+
+    #[test]
+    fn test_exec_projection() {
+        assert_eq!(
+            csv("SELECT city, name FROM people"),
+            "city,name\nParis,Alice\nLondon,Bob\nTokyo,Carol\nLondon,Dan\n"
+        );
+        assert_eq!(
+            csv("SELECT * FROM people LIMIT 1"),
+            "name,age,city\nAlice,34,Paris\n"
+        );
+    }
+
+    #[test]
+    fn test_exec_filter() {
+        assert_eq!(
+            csv("SELECT name FROM people WHERE age >= 18 AND city = 'London'"),
+            "name\nDan\n"
+        );
+        assert_eq!(
+            csv("SELECT name FROM people WHERE age < 20 OR NOT city != 'Tokyo'"),
+            "name\nBob\nCarol\n"
+        );
+        assert_eq!(csv("SELECT name FROM people WHERE age > 100"), "name\n");
+    }
+
+    #[test]
+    fn test_exec_order_by_and_limit() {
+        assert_eq!(
+            csv("SELECT name FROM people ORDER BY age DESC LIMIT 2"),
+            "name\nCarol\nAlice\n"
+        );
+        // Ties keep their original order, then the next key breaks them.
+        assert_eq!(
+            csv("SELECT name, city FROM people ORDER BY city"),
+            "name,city\nBob,London\nDan,London\nAlice,Paris\nCarol,Tokyo\n"
+        );
+        assert_eq!(
+            csv("SELECT name FROM people ORDER BY city DESC, name DESC"),
+            "name\nCarol\nAlice\nDan\nBob\n"
+        );
+        assert_eq!(csv("SELECT name FROM people LIMIT 0"), "name\n");
+    }
+
+    #[test]
+    fn test_exec_user_errors() {
+        let db = Database::sample();
+        assert_eq!(
+            db.run_query("SELECT name FROM persons"),
+            Err(QueryError::Parsing { row: 1, col: 18 })
+        );
+        assert_eq!(
+            db.run_query("SELECT name\nFROM people\nORDER BY height"),
+            Err(QueryError::Parsing { row: 3, col: 10 })
+        );
+        // Reported even though no rows would be compared.
+        let mut empty = Database::new();
+        empty.load_csv("t", "a,b\n").unwrap();
+        assert_eq!(
+            empty.run_query("SELECT a FROM t WHERE c = 1"),
+            Err(QueryError::Parsing { row: 1, col: 23 })
+        );
+        assert_eq!(
+            db.run_query("SELECT name FROM people WHERE name > 3"),
+            Err(QueryError::Parsing { row: 1, col: 36 })
+        );
+        // Also reported when the comparison would never run: `AND` skips it
+        // for every row, since no one is over 100.
+        assert_eq!(
+            db.run_query("SELECT name FROM people WHERE age > 100 AND city < age"),
+            Err(QueryError::Parsing { row: 1, col: 50 })
+        );
+    }
+
+    #[test]
+    fn test_exec_column_types() {
+        // One cell that isn't a number makes the whole column strings.
+        let mut db = Database::new();
+        db.load_csv("t", "id,code\n1,007\n2,A12\n3,NaN\n").unwrap();
+        assert_eq!(
+            db.run_query("SELECT id FROM t WHERE code = '007'")
+                .unwrap()
+                .to_csv(),
+            "id\n1\n"
+        );
+        assert_eq!(
+            db.run_query("SELECT id FROM t WHERE code = 7"),
+            Err(QueryError::Parsing { row: 1, col: 29 })
+        );
+        // With no rows there's nothing to tell the types from, so any
+        // comparison is allowed (and matches nothing).
+        db.load_csv("empty", "a,b\n").unwrap();
+        assert_eq!(
+            db.run_query("SELECT a FROM empty WHERE a = 'x' AND b > 1")
+                .unwrap()
+                .to_csv(),
+            "a\n"
+        );
+    }
+
+    #[test]
+    fn test_exec_server_error_on_broken_invariant() {
+        // `Table::new` and `Table::from_csv` won't build a ragged table, so
+        // reach in and break one by hand.
+        let mut db = Database::new();
+        db.insert(
+            "broken",
+            Table {
+                columns: vec!["a".to_owned(), "b".to_owned()],
+                rows: vec![vec![Value::Number(1.0)]],
+            },
+        );
+        assert_eq!(
+            db.run_query("SELECT b FROM broken WHERE a = 1"),
+            Err(QueryError::Server {
+                desc: "row has 1 cells but table `broken` has 2 columns".to_owned()
+            })
+        );
+        assert_eq!(
+            db.run_query("SELECT a FROM broken WHERE b = 1"),
+            Err(QueryError::Server {
+                desc: "column index 1 is out of range for a row of 1".to_owned()
+            })
+        );
+
+        // `Table::new` won't mix types in a column either.  A bad cell is
+        // reported with its row, not as a mistake in the query.
+        db.insert(
+            "mixed",
+            Table {
+                columns: vec!["a".to_owned()],
+                rows: vec![vec![Value::Number(1.0)], vec![Value::Str("x".to_owned())]],
+            },
+        );
+        assert_eq!(
+            db.run_query("SELECT a FROM mixed WHERE a > 0"),
+            Err(QueryError::Server {
+                desc: "row 1: cannot compare string with number".to_owned()
+            })
+        );
+    }
+}
@@ -0,0 +1,301 @@
+// Turns query text into tokens, remembering where each one started.
+
+use super::{CmpOp, ParseError, ParseErrorKind, Pos};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    Select,
+    From,
+    Where,
+    And,
+    Or,
+    Not,
+    Order,
+    By,
+    Asc,
+    Desc,
+    Limit,
+}
+
+impl Keyword {
+    fn from_word(word: &str) -> Option<Keyword> {
+        // Keywords are case-insensitive, like SQL.
+        let keyword = match word.to_ascii_uppercase().as_str() {
+            "SELECT" => Keyword::Select,
+            "FROM" => Keyword::From,
+            "WHERE" => Keyword::Where,
+            "AND" => Keyword::And,
+            "OR" => Keyword::Or,
+            "NOT" => Keyword::Not,
+            "ORDER" => Keyword::Order,
+            "BY" => Keyword::By,
+            "ASC" => Keyword::Asc,
+            "DESC" => Keyword::Desc,
+            "LIMIT" => Keyword::Limit,
+            _ => return None,
+        };
+        Some(keyword)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Keyword::Select => "SELECT",
+            Keyword::From => "FROM",
+            Keyword::Where => "WHERE",
+            Keyword::And => "AND",
+            Keyword::Or => "OR",
+            Keyword::Not => "NOT",
+            Keyword::Order => "ORDER",
+            Keyword::By => "BY",
+            Keyword::Asc => "ASC",
+            Keyword::Desc => "DESC",
+            Keyword::Limit => "LIMIT",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Keyword(Keyword),
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Op(CmpOp),
+    Star,
+    Comma,
+    LParen,
+    RParen,
+    /// Always the last token, positioned just past the end of the input.
+    End,
+}
+
+impl TokenKind {
+    /// How the token reads in an error message.
+    pub fn describe(&self) -> String {
+        match self {
+            TokenKind::Keyword(keyword) => format!("keyword {}", keyword.as_str()),
+            TokenKind::Ident(name) => format!("identifier `{name}`"),
+            TokenKind::Number(n) => format!("number {n}"),
+            TokenKind::Str(s) => format!("string '{s}'"),
+            TokenKind::Op(op) => format!("`{op}`"),
+            TokenKind::Star => "`*`".to_owned(),
+            TokenKind::Comma => "`,`".to_owned(),
+            TokenKind::LParen => "`(`".to_owned(),
+            TokenKind::RParen => "`)`".to_owned(),
+            TokenKind::End => "end of query".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub pos: Pos,
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    pos: Pos,
+}
+
+impl Lexer<'_> {
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.pos.row += 1;
+            self.pos.col = 1;
+        } else {
+            self.pos.col += 1;
+        }
+        Some(c)
+    }
+
+    fn error(pos: Pos, kind: ParseErrorKind) -> ParseError {
+        ParseError { pos, kind }
+    }
+
+    fn word(&mut self) -> TokenKind {
+        let mut word = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        {
+            word.push(c);
+            self.bump();
+        }
+        match Keyword::from_word(&word) {
+            Some(keyword) => TokenKind::Keyword(keyword),
+            None => TokenKind::Ident(word),
+        }
+    }
+
+    fn number(&mut self, start: Pos) -> Result<TokenKind, ParseError> {
+        let mut text = String::new();
+        if self.peek() == Some('-') {
+            text.push('-');
+            self.bump();
+        }
+        // `f64` parses "inf" and "NaN" too, which aren't numbers in a query.
+        let digit_led = self.peek().is_some_and(|c| c.is_ascii_digit());
+        // Grab anything number-ish, so that `1.2.3` or `12abc` is reported as a
+        // single bad number rather than a confusing sequence of tokens.  A sign
+        // right after an `e` is part of an exponent, as in `1e-5`.
+        while let Some(c) = self.peek().filter(|&c| {
+            c.is_ascii_alphanumeric()
+                || c == '.'
+                || (matches!(c, '+' | '-') && text.ends_with(['e', 'E']))
+        }) {
+            text.push(c);
+            self.bump();
+        }
+        if !digit_led {
+            return Err(Self::error(start, ParseErrorKind::InvalidNumber(text)));
+        }
+        text.parse()
+            .map(TokenKind::Number)
+            .map_err(|_| Self::error(start, ParseErrorKind::InvalidNumber(text)))
+    }
+
+    fn string(&mut self, start: Pos) -> Result<TokenKind, ParseError> {
+        // Skip the opening quote.
+        self.bump();
+        let mut contents = String::new();
+        loop {
+            match self.bump() {
+                // A doubled quote is an escaped quote, as in SQL.
+                Some('\'') if self.peek() == Some('\'') => {
+                    self.bump();
+                    contents.push('\'');
+                }
+                Some('\'') => return Ok(TokenKind::Str(contents)),
+                Some(c) => contents.push(c),
+                None => return Err(Self::error(start, ParseErrorKind::UnterminatedString)),
+            }
+        }
+    }
+
+    fn op(&mut self, start: Pos, first: char) -> Result<TokenKind, ParseError> {
+        self.bump();
+        let followed_by_eq = self.peek() == Some('=');
+        let op = match (first, followed_by_eq) {
+            ('=', _) => CmpOp::Eq,
+            ('!', true) => CmpOp::Ne,
+            ('<', true) => CmpOp::Le,
+            ('<', false) => CmpOp::Lt,
+            ('>', true) => CmpOp::Ge,
+            ('>', false) => CmpOp::Gt,
+            (c, _) => return Err(Self::error(start, ParseErrorKind::UnexpectedChar(c))),
+        };
+        if followed_by_eq && first != '=' {
+            self.bump();
+        }
+        Ok(TokenKind::Op(op))
+    }
+
+    fn next_token(&mut self) -> Result<Token, ParseError> {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+        let pos = self.pos;
+        let Some(c) = self.peek() else {
+            return Ok(Token {
+                kind: TokenKind::End,
+                pos,
+            });
+        };
+        let kind = match c {
+            c if c.is_ascii_alphabetic() || c == '_' => self.word(),
+            c if c.is_ascii_digit() || c == '-' => self.number(pos)?,
+            '\'' => self.string(pos)?,
+            '=' | '!' | '<' | '>' => self.op(pos, c)?,
+            '*' | ',' | '(' | ')' => {
+                self.bump();
+                match c {
+                    '*' => TokenKind::Star,
+                    ',' => TokenKind::Comma,
+                    '(' => TokenKind::LParen,
+                    _ => TokenKind::RParen,
+                }
+            }
+            c => return Err(Self::error(pos, ParseErrorKind::UnexpectedChar(c))),
+        };
+        Ok(Token { kind, pos })
+    }
+}
+
+/// Split a query into tokens.  The last token is always `TokenKind::End`.
+pub fn tokenize(query: &str) -> Result<Vec<Token>, ParseError> {
+    let mut lexer = Lexer {
+        chars: query.chars().peekable(),
+        pos: Pos { row: 1, col: 1 },
+    };
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token()?;
+        let done = token.kind == TokenKind::End;
+        tokens.push(token);
+        if done {
+            return Ok(tokens);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(query: &str) -> Vec<TokenKind> {
+        tokenize(query)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_kinds() {
+        assert_eq!(
+            kinds("select name, -1.5 FROM t where x <= 'it''s'"),
+            vec![
+                TokenKind::Keyword(Keyword::Select),
+                TokenKind::Ident("name".to_owned()),
+                TokenKind::Comma,
+                TokenKind::Number(-1.5),
+                TokenKind::Keyword(Keyword::From),
+                TokenKind::Ident("t".to_owned()),
+                TokenKind::Keyword(Keyword::Where),
+                TokenKind::Ident("x".to_owned()),
+                TokenKind::Op(CmpOp::Le),
+                TokenKind::Str("it's".to_owned()),
+                TokenKind::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_positions() {
+        let tokens = tokenize("SELECT *\n  FROM t").unwrap();
+        let positions: Vec<_> = tokens.iter().map(|t| (t.pos.row, t.pos.col)).collect();
+        assert_eq!(positions, vec![(1, 1), (1, 8), (2, 3), (2, 8), (2, 9)]);
+    }
+
+    #[test]
+    fn test_tokenize_operators() {
+        assert_eq!(
+            kinds("= != < <= > >="),
+            vec![
+                TokenKind::Op(CmpOp::Eq),
+                TokenKind::Op(CmpOp::Ne),
+                TokenKind::Op(CmpOp::Lt),
+                TokenKind::Op(CmpOp::Le),
+                TokenKind::Op(CmpOp::Gt),
+                TokenKind::Op(CmpOp::Ge),
+                TokenKind::End,
+            ]
+        );
+    }
+}
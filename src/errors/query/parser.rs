@@ -0,0 +1,213 @@
+// A recursive-descent parser, one function per grammar rule:
+//
+// query    := SELECT columns FROM ident [WHERE expr] [ORDER BY order (',' order)*] [LIMIT number]
+// columns  := '*' | ident (',' ident)*
+// order    := ident [ASC | DESC]
+// expr     := and_expr (OR and_expr)*
+// and_expr := not_expr (AND not_expr)*
+// not_expr := NOT not_expr | '(' expr ')' | operand op operand
+// operand  := ident | number | string
+
+use super::lexer::{tokenize, Keyword, Token, TokenKind};
+use super::{Columns, Expr, Ident, Operand, OrderBy, ParseError, ParseErrorKind, Query};
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        // `tokenize` guarantees a trailing `End`, and we never advance past it.
+        &self.tokens[self.next]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].clone();
+        if token.kind != TokenKind::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    /// Build the error for finding the next token when we wanted `expected`.
+    fn unexpected(&self, expected: &'static str) -> ParseError {
+        let token = self.peek();
+        let kind = match &token.kind {
+            TokenKind::End => ParseErrorKind::UnexpectedEnd { expected },
+            found => ParseErrorKind::UnexpectedToken {
+                expected,
+                found: found.describe(),
+            },
+        };
+        ParseError {
+            pos: token.pos,
+            kind,
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: Keyword) -> bool {
+        if self.peek().kind == TokenKind::Keyword(keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(
+        &mut self,
+        keyword: Keyword,
+        expected: &'static str,
+    ) -> Result<(), ParseError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    fn ident(&mut self, expected: &'static str) -> Result<Ident, ParseError> {
+        match self.peek().kind.clone() {
+            TokenKind::Ident(name) => {
+                let pos = self.advance().pos;
+                Ok(Ident { name, pos })
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query, ParseError> {
+        self.expect_keyword(Keyword::Select, "SELECT")?;
+        let columns = self.columns()?;
+        self.expect_keyword(Keyword::From, "FROM or `,`")?;
+        let table = self.ident("a table name")?;
+        let filter = if self.eat_keyword(Keyword::Where) {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+        let mut order_by = Vec::new();
+        if self.eat_keyword(Keyword::Order) {
+            self.expect_keyword(Keyword::By, "BY")?;
+            order_by.push(self.order()?);
+            while self.peek().kind == TokenKind::Comma {
+                self.advance();
+                order_by.push(self.order()?);
+            }
+        }
+        let limit = if self.eat_keyword(Keyword::Limit) {
+            Some(self.limit()?)
+        } else {
+            None
+        };
+        if self.peek().kind != TokenKind::End {
+            return Err(self.unexpected("end of query"));
+        }
+        Ok(Query {
+            columns,
+            table,
+            filter,
+            order_by,
+            limit,
+        })
+    }
+
+    fn order(&mut self) -> Result<OrderBy, ParseError> {
+        let column = self.ident("a column name")?;
+        let descending = if self.eat_keyword(Keyword::Desc) {
+            true
+        } else {
+            self.eat_keyword(Keyword::Asc);
+            false
+        };
+        Ok(OrderBy { column, descending })
+    }
+
+    fn limit(&mut self) -> Result<usize, ParseError> {
+        match self.peek().kind {
+            TokenKind::Number(n) if n >= 0.0 && n.fract() == 0.0 => {
+                self.advance();
+                Ok(n as usize)
+            }
+            _ => Err(self.unexpected("a non-negative whole number")),
+        }
+    }
+
+    fn columns(&mut self) -> Result<Columns, ParseError> {
+        if self.peek().kind == TokenKind::Star {
+            self.advance();
+            return Ok(Columns::All);
+        }
+        let mut names = vec![self.ident("`*` or a column name")?];
+        while self.peek().kind == TokenKind::Comma {
+            self.advance();
+            names.push(self.ident("a column name")?);
+        }
+        Ok(Columns::Named(names))
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.and_expr()?;
+        while self.eat_keyword(Keyword::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.not_expr()?;
+        while self.eat_keyword(Keyword::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.not_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr, ParseError> {
+        if self.eat_keyword(Keyword::Not) {
+            return Ok(Expr::Not(Box::new(self.not_expr()?)));
+        }
+        if self.peek().kind == TokenKind::LParen {
+            self.advance();
+            let expr = self.expr()?;
+            if self.peek().kind != TokenKind::RParen {
+                return Err(self.unexpected("`)`"));
+            }
+            self.advance();
+            return Ok(expr);
+        }
+        let left = self.operand()?;
+        let op_pos = self.peek().pos;
+        let op = match self.peek().kind {
+            TokenKind::Op(op) => {
+                self.advance();
+                op
+            }
+            _ => return Err(self.unexpected("a comparison operator")),
+        };
+        let right = self.operand()?;
+        Ok(Expr::Compare {
+            left,
+            op,
+            right,
+            pos: op_pos,
+        })
+    }
+
+    fn operand(&mut self) -> Result<Operand, ParseError> {
+        let operand = match self.peek().kind.clone() {
+            TokenKind::Ident(_) => return self.ident("a column name").map(Operand::Column),
+            TokenKind::Number(n) => Operand::Number(n),
+            TokenKind::Str(s) => Operand::Str(s),
+            _ => return Err(self.unexpected("a column name, number or string")),
+        };
+        self.advance();
+        Ok(operand)
+    }
+}
+
+/// Parse a query, reporting the position of the first syntax error.
+pub fn parse(query: &str) -> Result<Query, ParseError> {
+    let tokens = tokenize(query)?;
+    Parser { tokens, next: 0 }.query()
+}
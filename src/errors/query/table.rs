@@ -0,0 +1,300 @@
+// In-memory tables, loaded from (and written back to) CSV text.
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Str(String),
+}
+
+impl Value {
+    /// Cells that look like numbers are numbers; everything else is a string.
+    fn from_cell(cell: String) -> Value {
+        match parse_number(&cell) {
+            Some(n) => Value::Number(n),
+            None => Value::Str(cell),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "number",
+            Value::Str(_) => "string",
+        }
+    }
+
+    /// Compare two values of the same type; `None` if the types differ.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => Some(a.total_cmp(b)),
+            (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+
+    /// A total order for sorting, where every number sorts before every string.
+    pub fn sort_cmp(&self, other: &Value) -> Ordering {
+        self.compare(other).unwrap_or(match self {
+            Value::Number(_) => Ordering::Less,
+            Value::Str(_) => Ordering::Greater,
+        })
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{n}"),
+            Value::Str(s) if s.contains([',', '"']) => write!(f, "\"{}\"", s.replace('"', "\"\"")),
+            Value::Str(s) => f.write_str(s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CsvError {
+    #[error("CSV text has no header row")]
+    MissingHeader,
+    #[error("line {line} has {found} fields, but the header has {expected}")]
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("line {line} has an unterminated quote")]
+    UnterminatedQuote { line: usize },
+    #[error("column `{column}` has both numbers and strings")]
+    MixedTypes { column: String },
+}
+
+/// A table whose rows all have exactly one cell per column, and whose
+/// columns each hold only numbers or only strings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    // Visible to the rest of `query` so the executor's tests can break the
+    // invariant on purpose.
+    pub(super) columns: Vec<String>,
+    pub(super) rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn new(columns: Vec<String>, rows: Vec<Vec<Value>>) -> Result<Table, CsvError> {
+        for (i, row) in rows.iter().enumerate() {
+            if row.len() != columns.len() {
+                return Err(CsvError::RaggedRow {
+                    // +1 for the header, +1 for 1-based lines.
+                    line: i + 2,
+                    expected: columns.len(),
+                    found: row.len(),
+                });
+            }
+        }
+        let table = Table { columns, rows };
+        for (i, column) in table.columns.iter().enumerate() {
+            let first = table.column_type(i);
+            if table
+                .rows
+                .iter()
+                .any(|row| Some(row[i].type_name()) != first)
+            {
+                return Err(CsvError::MixedTypes {
+                    column: column.clone(),
+                });
+            }
+        }
+        Ok(table)
+    }
+
+    /// Parse CSV text with a header row.  Fields may be double-quoted, with
+    /// `""` for a literal quote, but may not span lines.  Blank lines are skipped.
+    ///
+    /// A column is numbers if every cell in it looks like one, and strings
+    /// otherwise, so the types don't depend on which rows a query looks at.
+    pub fn from_csv(text: &str) -> Result<Table, CsvError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty());
+        let (line, header) = lines.next().ok_or(CsvError::MissingHeader)?;
+        let columns = split_csv_line(header, line)?;
+        let mut rows = Vec::new();
+        for (line, text) in lines {
+            let row = split_csv_line(text, line)?;
+            if row.len() != columns.len() {
+                return Err(CsvError::RaggedRow {
+                    line,
+                    expected: columns.len(),
+                    found: row.len(),
+                });
+            }
+            rows.push(row);
+        }
+        let numeric: Vec<bool> = (0..columns.len())
+            .map(|i| rows.iter().all(|row| parse_number(&row[i]).is_some()))
+            .collect();
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .zip(&numeric)
+                    .map(|(cell, &numeric)| match numeric {
+                        true => Value::from_cell(cell),
+                        false => Value::Str(cell),
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(Table { columns, rows })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = self.columns.join(",");
+        csv.push('\n');
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(Value::to_string).collect();
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn rows(&self) -> &[Vec<Value>] {
+        &self.rows
+    }
+
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+
+    /// "number" or "string", or `None` if the table has no rows to tell.
+    pub fn column_type(&self, i: usize) -> Option<&'static str> {
+        self.rows.first()?.get(i).map(Value::type_name)
+    }
+}
+
+/// Rust's `f64` parser also takes "NaN" and "inf", which are much more likely
+/// to be somebody's name, so a number must start with a digit.
+fn parse_number(cell: &str) -> Option<f64> {
+    let digits = cell.trim_start_matches(['+', '-']);
+    let digits = digits.strip_prefix('.').unwrap_or(digits);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    cell.parse().ok()
+}
+
+fn split_csv_line(text: &str, line: usize) -> Result<Vec<String>, CsvError> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', _) => in_quotes = !in_quotes,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            (c, _) => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(CsvError::UnterminatedQuote { line });
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_from_csv() {
+        let table = Table::from_csv("name,age\nAlice,34\n\n\"Smith, Bob\",17\n").unwrap();
+        assert_eq!(table.columns(), ["name", "age"]);
+        assert_eq!(
+            table.rows(),
+            [
+                vec![Value::Str("Alice".to_owned()), Value::Number(34.0)],
+                vec![Value::Str("Smith, Bob".to_owned()), Value::Number(17.0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_table_number_cells() {
+        for (cell, n) in [("-3", -3.0), (".5", 0.5), ("+1e3", 1000.0)] {
+            assert_eq!(Value::from_cell(cell.to_owned()), Value::Number(n));
+        }
+        for cell in ["NaN", "nan", "inf", "-Infinity", "1.2.3", "."] {
+            assert_eq!(
+                Value::from_cell(cell.to_owned()),
+                Value::Str(cell.to_owned())
+            );
+        }
+        // So someone called Nan is still a string, and can be compared as one.
+        let table = Table::from_csv("name,age\nNan,70\n").unwrap();
+        assert_eq!(table.rows()[0][0], Value::Str("Nan".to_owned()));
+        assert_eq!(table.column_type(0), Some("string"));
+        assert_eq!(table.column_type(1), Some("number"));
+
+        // A column is only numbers if every cell is one, and the rest keep
+        // their text exactly.
+        let table = Table::from_csv("zip\n01234\nSW1A\n").unwrap();
+        assert_eq!(
+            table.rows(),
+            [
+                vec![Value::Str("01234".to_owned())],
+                vec![Value::Str("SW1A".to_owned())]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_table_csv_round_trip() {
+        let csv = "name,quote\nAlice,\"She said \"\"hi\"\", then left\"\nBob,2.5\n";
+        assert_eq!(Table::from_csv(csv).unwrap().to_csv(), csv);
+    }
+
+    #[test]
+    fn test_table_csv_errors() {
+        assert_eq!(Table::from_csv("\n\n"), Err(CsvError::MissingHeader));
+        assert_eq!(
+            Table::from_csv("a,b\n1,2\n\n3\n"),
+            Err(CsvError::RaggedRow {
+                line: 4,
+                expected: 2,
+                found: 1,
+            })
+        );
+        assert_eq!(
+            Table::from_csv("a,b\n\"1,2\n"),
+            Err(CsvError::UnterminatedQuote { line: 2 })
+        );
+        assert_eq!(
+            Table::new(
+                vec!["a".to_owned()],
+                vec![vec![Value::Number(1.0)], vec![Value::Str("1".to_owned())]]
+            ),
+            Err(CsvError::MixedTypes {
+                column: "a".to_owned()
+            })
+        );
+        assert_eq!(
+            Table::new(vec!["a".to_owned()], vec![vec![]]),
+            Err(CsvError::RaggedRow {
+                line: 2,
+                expected: 1,
+                found: 0,
+            })
+        );
+    }
+}
@@ -0,0 +1,315 @@
+// Retrying queries that fail for transient reasons.
+//
+// `QueryError::Network` is documented as "may be transient; retry soon".
+// Because the error is structured, the retry loop can tell it apart from a
+// `Parsing` error, which will fail the same way however many times we try.
+
+use std::time::Duration;
+
+use super::query::QueryError;
+
+/// Something that can wait.  Tests use a fake that just records the delays.
+pub trait Clock {
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// The real clock: actually sleeps.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one.  Must be at least 1.
+    pub max_attempts: u32,
+    /// The wait after the first failure; it doubles after each later one.
+    pub initial_backoff: Duration,
+    /// No single wait is longer than this.
+    pub max_backoff: Duration,
+    /// Fraction of each wait that is randomized away, from 0.0 (none) to 1.0
+    /// (anywhere from zero to the full wait).  This keeps many clients that
+    /// failed together from all retrying at the same moment.  Values outside
+    /// that range are clamped to it, and NaN counts as 0.0.
+    pub jitter: f64,
+    /// Seed for the jitter, so tests can be deterministic.
+    pub seed: u64,
+    /// Which errors are worth retrying.
+    pub retryable: fn(&QueryError) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        RetryPolicy {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            jitter: 0.5,
+            seed,
+            retryable: QueryError::is_transient,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait after failed attempt number `attempt` (starting at 1),
+    /// given a random number `rand` in `[0, 1)`.
+    pub fn backoff(&self, attempt: u32, rand: f64) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(31);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff);
+        // `clamp` passes NaN through, and `mul_f64` would panic on it.
+        let jitter = match self.jitter.is_nan() {
+            true => 0.0,
+            false => self.jitter.clamp(0.0, 1.0),
+        };
+        backoff.mul_f64(1.0 - jitter * rand)
+    }
+
+    /// Call `op` until it succeeds, fails with an error that isn't retryable,
+    /// or runs out of attempts.  Returns the last result either way.
+    pub fn retry<T>(
+        &self,
+        clock: &mut impl Clock,
+        mut op: impl FnMut() -> Result<T, QueryError>,
+    ) -> Result<T, QueryError> {
+        assert!(
+            self.max_attempts > 0,
+            "RetryPolicy needs at least 1 attempt"
+        );
+        let mut rng = XorShift(self.seed | 1);
+        let mut attempt = 1;
+        loop {
+            match op() {
+                Err(err) if attempt < self.max_attempts && (self.retryable)(&err) => {
+                    clock.sleep(self.backoff(attempt, rng.next_f64()));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Retry `op` with the default policy, really sleeping between attempts.
+pub fn with_retry<T>(op: impl FnMut() -> Result<T, QueryError>) -> Result<T, QueryError> {
+    RetryPolicy::default().retry(&mut SystemClock, op)
+}
+
+/// A tiny PRNG; plenty for jitter, and saves a dependency.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        // The top 53 bits fill an f64's mantissa exactly.
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::query::{run_query, Table};
+    use super::*;
+
+    #[derive(Default)]
+    struct FakeClock {
+        sleeps: Vec<Duration>,
+    }
+
+    impl Clock for FakeClock {
+        fn sleep(&mut self, duration: Duration) {
+            self.sleeps.push(duration);
+        }
+    }
+
+    /// Fails the first `failures` calls with `error`, then runs the query for real.
+    struct FakeBackend {
+        failures: u32,
+        error: QueryError,
+        calls: u32,
+    }
+
+    impl FakeBackend {
+        fn new(failures: u32, error: QueryError) -> Self {
+            FakeBackend {
+                failures,
+                error,
+                calls: 0,
+            }
+        }
+
+        fn query(&mut self, query: &str) -> Result<Table, QueryError> {
+            self.calls += 1;
+            if self.calls <= self.failures {
+                return Err(self.error.clone());
+            }
+            run_query(query)
+        }
+    }
+
+    fn unavailable() -> QueryError {
+        QueryError::Network {
+            http_code: 503,
+            reason: "Service Unavailable".to_owned(),
+        }
+    }
+
+    fn no_jitter() -> RetryPolicy {
+        RetryPolicy {
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_retry_succeeds_after_transient_failures() {
+        let mut clock = FakeClock::default();
+        let mut backend = FakeBackend::new(2, unavailable());
+        let result = no_jitter().retry(&mut clock, || {
+            backend.query("SELECT name FROM people WHERE age > 50")
+        });
+        assert_eq!(result.unwrap().to_csv(), "name\nCarol\n");
+        assert_eq!(backend.calls, 3);
+        assert_eq!(
+            clock.sleeps,
+            vec![Duration::from_millis(100), Duration::from_millis(200)]
+        );
+    }
+
+    #[test]
+    fn test_retry_gives_up_after_max_attempts() {
+        let mut clock = FakeClock::default();
+        let mut backend = FakeBackend::new(10, unavailable());
+        let result = no_jitter().retry(&mut clock, || backend.query("SELECT * FROM people"));
+        assert_eq!(result, Err(unavailable()));
+        assert_eq!(backend.calls, 4);
+        assert_eq!(clock.sleeps.len(), 3);
+    }
+
+    #[test]
+    fn test_retry_skips_non_transient_errors() {
+        let mut clock = FakeClock::default();
+        let server_error = QueryError::Server {
+            desc: "oops".to_owned(),
+        };
+        let mut backend = FakeBackend::new(1, server_error.clone());
+        let result = no_jitter().retry(&mut clock, || backend.query("SELECT * FROM people"));
+        assert_eq!(result, Err(server_error));
+        assert_eq!(backend.calls, 1);
+
+        // A real parsing error is never retried either.
+        let mut calls = 0;
+        let result = no_jitter().retry(&mut clock, || {
+            calls += 1;
+            run_query("SELECT * FROM")
+        });
+        assert_eq!(result, Err(QueryError::Parsing { row: 1, col: 14 }));
+        assert_eq!(calls, 1);
+        assert!(clock.sleeps.is_empty());
+    }
+
+    #[test]
+    fn test_retry_custom_predicate() {
+        // Say our oncall tells us the server errors are flaky this week.
+        let policy = RetryPolicy {
+            retryable: |err| matches!(err, QueryError::Server { .. }),
+            ..no_jitter()
+        };
+        let mut clock = FakeClock::default();
+        let mut backend = FakeBackend::new(
+            1,
+            QueryError::Server {
+                desc: "flaky".to_owned(),
+            },
+        );
+        assert!(policy
+            .retry(&mut clock, || backend.query("SELECT * FROM people"))
+            .is_ok());
+        assert_eq!(backend.calls, 2);
+
+        // ...but now network errors aren't retried.
+        let mut backend = FakeBackend::new(1, unavailable());
+        assert_eq!(
+            policy.retry(&mut clock, || backend.query("SELECT * FROM people")),
+            Err(unavailable())
+        );
+    }
+
+    #[test]
+    fn test_retry_backoff_is_capped() {
+        let policy = RetryPolicy {
+            max_backoff: Duration::from_millis(500),
+            ..no_jitter()
+        };
+        let backoffs: Vec<_> = (1..=5)
+            .map(|attempt| policy.backoff(attempt, 0.0))
+            .collect();
+        assert_eq!(
+            backoffs,
+            [100, 200, 400, 500, 500].map(Duration::from_millis)
+        );
+        // Huge attempt counts saturate rather than overflow.
+        assert_eq!(policy.backoff(1000, 0.0), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_retry_jitter() {
+        let policy = RetryPolicy {
+            max_attempts: 6,
+            jitter: 0.5,
+            seed: 42,
+            ..RetryPolicy::default()
+        };
+        let mut clock = FakeClock::default();
+        let mut backend = FakeBackend::new(5, unavailable());
+        assert!(policy
+            .retry(&mut clock, || backend.query("SELECT * FROM people"))
+            .is_ok());
+        assert_eq!(clock.sleeps.len(), 5);
+        for (i, sleep) in clock.sleeps.iter().enumerate() {
+            let full = policy.backoff(i as u32 + 1, 0.0);
+            assert!(
+                *sleep <= full && *sleep >= full / 2,
+                "{sleep:?} vs {full:?}"
+            );
+        }
+
+        // The same seed gives the same delays.
+        let mut again = FakeClock::default();
+        let mut backend = FakeBackend::new(5, unavailable());
+        let _ = policy.retry(&mut again, || backend.query("SELECT * FROM people"));
+        assert_eq!(clock.sleeps, again.sleeps);
+    }
+
+    #[test]
+    fn test_retry_jitter_out_of_range() {
+        let policy = |jitter| RetryPolicy {
+            jitter,
+            ..no_jitter()
+        };
+        let full = Duration::from_millis(100);
+        // NaN means no jitter, rather than a panic.
+        assert_eq!(policy(f64::NAN).backoff(1, 0.5), full);
+        assert_eq!(policy(-1.0).backoff(1, 0.5), full);
+        assert_eq!(policy(3.0).backoff(1, 0.5), full / 2);
+    }
+
+    #[test]
+    fn test_with_retry() {
+        // Succeeds first time, so it never actually sleeps.
+        let table = with_retry(|| run_query("SELECT name FROM people LIMIT 1")).unwrap();
+        assert_eq!(table.to_csv(), "name\nAlice\n");
+    }
+}
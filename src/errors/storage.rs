@@ -0,0 +1,129 @@
+// The bottom layer: reading table files.  Its errors wrap `std::io::Error`
+// and `CsvError`, keeping them as the `source()` rather than flattening them
+// into a message, so callers can still inspect them.
+
+use std::io::Read;
+use std::sync::Arc;
+
+use super::query::{CsvError, Table};
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum StorageError {
+    // `io::Error` isn't `Clone`, so share it.
+    #[error("could not read `{path}`")]
+    Read {
+        path: String,
+        #[source]
+        source: Arc<std::io::Error>,
+    },
+    #[error("`{path}` is not valid CSV")]
+    Csv {
+        path: String,
+        #[source]
+        source: CsvError,
+    },
+}
+
+// `io::Error` isn't `PartialEq` either, so compare what we can see of it.
+impl PartialEq for StorageError {
+    fn eq(&self, other: &Self) -> bool {
+        use StorageError::*;
+        match (self, other) {
+            (Read { path, source }, Read { path: p, source: s }) => {
+                path == p && source.kind() == s.kind() && source.to_string() == s.to_string()
+            }
+            (Csv { path, source }, Csv { path: p, source: s }) => path == p && source == s,
+            _ => false,
+        }
+    }
+}
+
+impl StorageError {
+    /// Whether reading again might work: the read was cut short, rather than
+    /// the file being unreadable or its contents invalid.
+    pub fn is_transient(&self) -> bool {
+        use std::io::ErrorKind::*;
+        match self {
+            // No `Interrupted`: `read_to_string` retries those itself.
+            StorageError::Read { source, .. } => {
+                matches!(source.kind(), TimedOut | WouldBlock)
+            }
+            StorageError::Csv { .. } => false,
+        }
+    }
+}
+
+/// Read a CSV table.  `path` is only used to describe errors.
+pub fn read_table(path: &str, mut reader: impl Read) -> Result<Table, StorageError> {
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .map_err(|source| StorageError::Read {
+            path: path.to_owned(),
+            source: Arc::new(source),
+        })?;
+    Table::from_csv(&text).map_err(|source| StorageError::Csv {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_storage_read_table() {
+        let table = read_table("t.csv", "a,b\n1,2\n".as_bytes()).unwrap();
+        assert_eq!(table.to_csv(), "a,b\n1,2\n");
+    }
+
+    #[test]
+    fn test_storage_errors_keep_source() {
+        let err = read_table("t.csv", "a,b\n1\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "`t.csv` is not valid CSV");
+        assert_eq!(
+            err.source().map(|e| e.to_string()),
+            Some("line 2 has 1 fields, but the header has 2".to_owned())
+        );
+
+        // Not UTF-8, so `read_to_string` fails with an I/O error.
+        let err = read_table("bin.csv", [0xff, 0xfe].as_slice()).unwrap_err();
+        assert_eq!(err.to_string(), "could not read `bin.csv`");
+        let source = err.source().unwrap();
+        // The source is the `Arc` itself, so that's what we downcast to.
+        let io_err = source.downcast_ref::<Arc<std::io::Error>>().unwrap();
+        assert_eq!(io_err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_storage_transient_errors() {
+        use std::io::ErrorKind;
+
+        // A reader that always fails the same way.
+        struct Failing(ErrorKind);
+        impl Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(self.0.into())
+            }
+        }
+
+        let transient = |kind| {
+            read_table("t.csv", Failing(kind))
+                .unwrap_err()
+                .is_transient()
+        };
+        assert!(transient(ErrorKind::TimedOut));
+        assert!(transient(ErrorKind::WouldBlock));
+        assert!(!transient(ErrorKind::NotFound));
+        assert!(!transient(ErrorKind::PermissionDenied));
+        // And so does a query that failed to load its table.
+        let err = read_table("t.csv", Failing(ErrorKind::TimedOut)).unwrap_err();
+        assert!(super::super::query::QueryError::from(err).is_transient());
+        // Bad CSV stays bad, however often it's read.
+        assert!(!read_table("t.csv", "a\n1,2\n".as_bytes())
+            .unwrap_err()
+            .is_transient());
+    }
+}
@@ -1,3 +1,6 @@
+// The HTTP response builder used in `exercise_generics_typestate`.
+pub mod http;
+
 #[cfg(test)]
 mod tests {
     // Rust has Generic types, which are better structured and more powerful
@@ -129,68 +132,26 @@ mod tests {
         // Generics are _really_ powerful, sometimes bordering on sorcery.
         // Check out what you can do here.
 
-        use std::collections::HashMap;
-
-        struct HttpResponseBuilder<STATE> {
-            state: STATE,
-        }
-        struct New;
-        struct Headers {
-            status_code: u16,
-            headers: HashMap<String, String>,
-        }
-        struct HttpResponse {
-            status_code: u16,
-            headers: HashMap<String, String>,
-            body: String,
-        }
-
-        impl HttpResponseBuilder<New> {
-            pub fn new() -> Self {
-                HttpResponseBuilder { state: New }
-            }
-
-            pub fn status(self, code: u16) -> HttpResponseBuilder<Headers> {
-                HttpResponseBuilder {
-                    state: Headers {
-                        status_code: code,
-                        headers: HashMap::new(),
-                    },
-                }
-            }
-        }
-
-        impl HttpResponseBuilder<Headers> {
-            pub fn add_header(mut self, key: String, val: String) -> Self {
-                self.state.headers.insert(key, val);
-                self
-            }
-
-            pub fn body(self, contents: String) -> HttpResponse {
-                let Headers {
-                    status_code,
-                    headers,
-                } = self.state;
-                HttpResponse {
-                    status_code,
-                    headers,
-                    body: contents,
-                }
-            }
-        }
+        // The builder lives in `generics/http.rs`; go read it first.  Notice that
+        // each state is its own type, and each method consumes the builder and
+        // returns it in the next state.
+        // `status` takes a `StatusCode` rather than a `u16`, so `.status(1234)`
+        // can't even be written.
 
         unimplemented!(
             r#"
             This pattern enforces at compile-type the correct order of operations.
             Build an HttpResponse `response` using HttpResponseBuilder to make
-            these asserts pass.
+            these asserts pass.  Then try calling the methods out of order (say,
+            `add_trailer` after `body`) and read the compiler error.
             "#
         );
 
+        // use super::http::*;
         // BUILD ME HERE
 
-        // assert_eq!(response.status_code, 200);
-        // assert_eq!(response.headers.get("foo", "bar"));
+        // assert_eq!(response.status_code, StatusCode::OK);
+        // assert_eq!(response.headers.get("foo"), Some("bar"));
         // assert_eq!(response.body, "snoopy");
     }
 
@@ -0,0 +1,605 @@
+// The typestate HTTP response builder from `exercise_generics_typestate`.
+// It started life inside the test, but it's useful enough to live here.
+
+/// A case-insensitive, multi-valued map of HTTP headers.
+///
+/// HTTP header names are case-insensitive, so `Content-Type` and `content-type`
+/// are the same header.  Some headers (like `Set-Cookie`) may also appear more
+/// than once, so a plain `HashMap<String, String>` would lose data.  Entries
+/// are kept in insertion order, and the name keeps the casing it was first
+/// given with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a value for `key`, keeping any values that are already there.
+    pub fn append(&mut self, key: impl Into<String>, val: impl Into<String>) {
+        let key = key.into();
+        // Reuse the casing of an existing entry, so iteration is consistent.
+        let key = match self
+            .entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(&key))
+        {
+            Some((existing, _)) => existing.clone(),
+            None => key,
+        };
+        self.entries.push((key, val.into()));
+    }
+
+    /// Set the value for `key`, replacing all previous values.
+    /// Returns the first of the replaced values, if there were any.
+    pub fn insert(&mut self, key: impl Into<String>, val: impl Into<String>) -> Option<String> {
+        let key = key.into();
+        let previous = self.remove(&key).into_iter().next();
+        self.append(key, val);
+        previous
+    }
+
+    /// The first value for `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// All values for `key`, in the order they were added.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Remove every value for `key`, returning them in the order they were added.
+    pub fn remove(&mut self, key: &str) -> Vec<String> {
+        let (removed, kept) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|(k, _)| k.eq_ignore_ascii_case(key));
+        self.entries = kept;
+        removed.into_iter().map(|(_, v)| v).collect()
+    }
+
+    /// The number of values (not distinct names) in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every `(name, value)` pair, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// An HTTP status code.
+///
+/// The inner `u16` is private, so the only ways to get a `StatusCode` are the
+/// named constants below or `StatusCode::try_from`, which rejects anything
+/// outside `100..=599`.  An invalid status code is unrepresentable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StatusCode(u16);
+
+#[derive(Debug, PartialEq, thiserror::Error)]
+#[error("invalid status code {0}: must be in 100..=599")]
+pub struct InvalidStatusCode(u16);
+
+impl StatusCode {
+    pub const CONTINUE: StatusCode = StatusCode(100);
+    pub const SWITCHING_PROTOCOLS: StatusCode = StatusCode(101);
+    pub const OK: StatusCode = StatusCode(200);
+    pub const CREATED: StatusCode = StatusCode(201);
+    pub const ACCEPTED: StatusCode = StatusCode(202);
+    pub const NO_CONTENT: StatusCode = StatusCode(204);
+    pub const MOVED_PERMANENTLY: StatusCode = StatusCode(301);
+    pub const FOUND: StatusCode = StatusCode(302);
+    pub const NOT_MODIFIED: StatusCode = StatusCode(304);
+    pub const BAD_REQUEST: StatusCode = StatusCode(400);
+    pub const UNAUTHORIZED: StatusCode = StatusCode(401);
+    pub const FORBIDDEN: StatusCode = StatusCode(403);
+    pub const NOT_FOUND: StatusCode = StatusCode(404);
+    pub const METHOD_NOT_ALLOWED: StatusCode = StatusCode(405);
+    pub const CONFLICT: StatusCode = StatusCode(409);
+    pub const TOO_MANY_REQUESTS: StatusCode = StatusCode(429);
+    pub const INTERNAL_SERVER_ERROR: StatusCode = StatusCode(500);
+    pub const NOT_IMPLEMENTED: StatusCode = StatusCode(501);
+    pub const BAD_GATEWAY: StatusCode = StatusCode(502);
+    pub const SERVICE_UNAVAILABLE: StatusCode = StatusCode(503);
+    pub const GATEWAY_TIMEOUT: StatusCode = StatusCode(504);
+
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+
+    /// The canonical reason phrase, e.g. "Not Found" for 404.
+    /// Valid but unusual codes (like 299) don't have one.
+    pub fn canonical_reason(&self) -> Option<&'static str> {
+        let reason = match self.0 {
+            100 => "Continue",
+            101 => "Switching Protocols",
+            200 => "OK",
+            201 => "Created",
+            202 => "Accepted",
+            204 => "No Content",
+            301 => "Moved Permanently",
+            302 => "Found",
+            304 => "Not Modified",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            429 => "Too Many Requests",
+            500 => "Internal Server Error",
+            501 => "Not Implemented",
+            502 => "Bad Gateway",
+            503 => "Service Unavailable",
+            504 => "Gateway Timeout",
+            _ => return None,
+        };
+        Some(reason)
+    }
+
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.0)
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.0)
+    }
+
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.0)
+    }
+
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.0)
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.0)
+    }
+}
+
+impl TryFrom<u16> for StatusCode {
+    type Error = InvalidStatusCode;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        if (100..600).contains(&code) {
+            Ok(StatusCode(code))
+        } else {
+            Err(InvalidStatusCode(code))
+        }
+    }
+}
+
+impl std::fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.canonical_reason() {
+            Some(reason) => write!(f, "{} {reason}", self.0),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// Builds an `HttpResponse`, with the order of calls checked at compile time.
+///
+/// The states are `New -> Headers -> Body -> Trailers -> Done`.  A fixed body
+/// skips straight from `Headers` to `Done`; only a chunked body can be
+/// followed by trailers, since that's the only place HTTP/1.1 allows them.
+///
+/// ```
+/// use dlcm_rust_workshop::generics::http::*;
+///
+/// let response = HttpResponseBuilder::new()
+///     .status(StatusCode::OK)
+///     .add_header("Content-Type".to_owned(), "text/plain".to_owned())
+///     .chunked()
+///     .write_chunk("snoo".to_owned())
+///     .write_chunk("py".to_owned())
+///     .finish()
+///     .add_trailer("Expires".to_owned(), "never".to_owned())
+///     .build();
+/// assert_eq!(response.body, "snoopy");
+/// ```
+///
+/// Out-of-order calls don't compile.  You can't skip the status:
+///
+/// ```compile_fail
+/// use dlcm_rust_workshop::generics::http::*;
+///
+/// let response = HttpResponseBuilder::new().body("snoopy".to_owned()).build();
+/// ```
+///
+/// ...or set it twice:
+///
+/// ```compile_fail
+/// use dlcm_rust_workshop::generics::http::*;
+///
+/// let builder = HttpResponseBuilder::new()
+///     .status(StatusCode::OK)
+///     .status(StatusCode::NOT_FOUND);
+/// ```
+///
+/// Headers can't be added once the body has started:
+///
+/// ```compile_fail
+/// use dlcm_rust_workshop::generics::http::*;
+///
+/// let builder = HttpResponseBuilder::new()
+///     .status(StatusCode::OK)
+///     .chunked()
+///     .write_chunk("snoopy".to_owned())
+///     .add_header("Content-Type".to_owned(), "text/plain".to_owned());
+/// ```
+///
+/// Chunks can't be written after the body is finished:
+///
+/// ```compile_fail
+/// use dlcm_rust_workshop::generics::http::*;
+///
+/// let builder = HttpResponseBuilder::new()
+///     .status(StatusCode::OK)
+///     .chunked()
+///     .finish()
+///     .write_chunk("snoopy".to_owned());
+/// ```
+///
+/// And a fixed body can't have trailers:
+///
+/// ```compile_fail
+/// use dlcm_rust_workshop::generics::http::*;
+///
+/// let builder = HttpResponseBuilder::new()
+///     .status(StatusCode::OK)
+///     .body("snoopy".to_owned())
+///     .add_trailer("Expires".to_owned(), "never".to_owned());
+/// ```
+pub struct HttpResponseBuilder<STATE> {
+    state: STATE,
+}
+
+pub struct New;
+
+pub struct Headers {
+    status_code: StatusCode,
+    headers: HeaderMap,
+}
+
+/// A chunked body that is still being written.
+pub struct Body {
+    status_code: StatusCode,
+    headers: HeaderMap,
+    chunks: Vec<String>,
+}
+
+pub struct Trailers {
+    status_code: StatusCode,
+    headers: HeaderMap,
+    chunks: Vec<String>,
+    trailers: HeaderMap,
+}
+
+pub struct Done {
+    response: HttpResponse,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status_code: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+    /// The chunks the body was written in; empty for a fixed body.
+    pub chunks: Vec<String>,
+    /// Always empty for a fixed body.
+    pub trailers: HeaderMap,
+}
+
+impl HttpResponse {
+    pub fn is_chunked(&self) -> bool {
+        self.headers
+            .get_all("Transfer-Encoding")
+            .any(|v| v.eq_ignore_ascii_case("chunked"))
+    }
+}
+
+impl HttpResponseBuilder<New> {
+    pub fn new() -> Self {
+        HttpResponseBuilder { state: New }
+    }
+
+    pub fn status(self, code: StatusCode) -> HttpResponseBuilder<Headers> {
+        HttpResponseBuilder {
+            state: Headers {
+                status_code: code,
+                headers: HeaderMap::new(),
+            },
+        }
+    }
+}
+
+impl Default for HttpResponseBuilder<New> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpResponseBuilder<Headers> {
+    /// Add a header.  Repeated headers are kept, not overwritten.
+    pub fn add_header(mut self, key: String, val: String) -> Self {
+        self.state.headers.append(key, val);
+        self
+    }
+
+    /// Finish the response with a body that's known up front.
+    pub fn body(self, contents: String) -> HttpResponseBuilder<Done> {
+        let Headers {
+            status_code,
+            headers,
+        } = self.state;
+        HttpResponseBuilder {
+            state: Done {
+                response: HttpResponse {
+                    status_code,
+                    headers,
+                    body: contents,
+                    chunks: Vec::new(),
+                    trailers: HeaderMap::new(),
+                },
+            },
+        }
+    }
+
+    /// Start a chunked body, to be written piece by piece.
+    pub fn chunked(self) -> HttpResponseBuilder<Body> {
+        let Headers {
+            status_code,
+            mut headers,
+        } = self.state;
+        headers.insert("Transfer-Encoding", "chunked");
+        HttpResponseBuilder {
+            state: Body {
+                status_code,
+                headers,
+                chunks: Vec::new(),
+            },
+        }
+    }
+}
+
+impl HttpResponseBuilder<Body> {
+    pub fn write_chunk(mut self, chunk: String) -> Self {
+        self.state.chunks.push(chunk);
+        self
+    }
+
+    /// Finish the body.  Trailers may be added after this.
+    pub fn finish(self) -> HttpResponseBuilder<Trailers> {
+        let Body {
+            status_code,
+            headers,
+            chunks,
+        } = self.state;
+        HttpResponseBuilder {
+            state: Trailers {
+                status_code,
+                headers,
+                chunks,
+                trailers: HeaderMap::new(),
+            },
+        }
+    }
+}
+
+impl HttpResponseBuilder<Trailers> {
+    pub fn add_trailer(mut self, key: String, val: String) -> Self {
+        self.state.trailers.append(key, val);
+        self
+    }
+
+    pub fn build(self) -> HttpResponse {
+        let Trailers {
+            status_code,
+            headers,
+            chunks,
+            trailers,
+        } = self.state;
+        HttpResponse {
+            status_code,
+            headers,
+            body: chunks.concat(),
+            chunks,
+            trailers,
+        }
+    }
+}
+
+impl HttpResponseBuilder<Done> {
+    pub fn build(self) -> HttpResponse {
+        self.state.response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_map_case_insensitive() {
+        let mut headers = HeaderMap::new();
+        headers.append("Content-Type", "text/plain");
+        assert_eq!(headers.get("content-type"), Some("text/plain"));
+        assert_eq!(headers.get("CONTENT-TYPE"), Some("text/plain"));
+        assert!(headers.contains_key("content-TYPE"));
+        assert_eq!(headers.get("Content-Length"), None);
+    }
+
+    #[test]
+    fn test_header_map_multi_value() {
+        let mut headers = HeaderMap::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append("set-cookie", "b=2");
+        assert_eq!(headers.get("Set-Cookie"), Some("a=1"));
+        assert_eq!(
+            headers.get_all("SET-COOKIE").collect::<Vec<_>>(),
+            vec!["a=1", "b=2"]
+        );
+        assert_eq!(headers.len(), 2);
+    }
+
+    #[test]
+    fn test_header_map_insert_replaces() {
+        let mut headers = HeaderMap::new();
+        headers.append("Accept", "text/html");
+        headers.append("Accept", "text/plain");
+        assert_eq!(
+            headers.insert("accept", "application/json"),
+            Some("text/html".to_owned())
+        );
+        assert_eq!(
+            headers.get_all("Accept").collect::<Vec<_>>(),
+            vec!["application/json"]
+        );
+        assert_eq!(headers.insert("Host", "example.com"), None);
+    }
+
+    #[test]
+    fn test_header_map_remove() {
+        let mut headers = HeaderMap::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append("Host", "example.com");
+        headers.append("Set-Cookie", "b=2");
+        assert_eq!(headers.remove("set-cookie"), vec!["a=1", "b=2"]);
+        assert_eq!(headers.len(), 1);
+        assert!(headers.remove("set-cookie").is_empty());
+        headers.remove("host");
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn test_header_map_ordered_iteration() {
+        let mut headers = HeaderMap::new();
+        headers.append("Host", "example.com");
+        headers.append("Set-Cookie", "a=1");
+        headers.append("Accept", "*/*");
+        // Later entries reuse the casing of the first one.
+        headers.append("set-cookie", "b=2");
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            vec![
+                ("Host", "example.com"),
+                ("Set-Cookie", "a=1"),
+                ("Accept", "*/*"),
+                ("Set-Cookie", "b=2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_status_code_try_from() {
+        assert_eq!(StatusCode::try_from(404), Ok(StatusCode::NOT_FOUND));
+        assert_eq!(StatusCode::try_from(299).map(|c| c.as_u16()), Ok(299));
+        assert_eq!(StatusCode::try_from(99), Err(InvalidStatusCode(99)));
+        assert_eq!(StatusCode::try_from(600), Err(InvalidStatusCode(600)));
+        assert_eq!(
+            InvalidStatusCode(0).to_string(),
+            "invalid status code 0: must be in 100..=599"
+        );
+    }
+
+    #[test]
+    fn test_status_code_reason_phrases() {
+        assert_eq!(StatusCode::OK.canonical_reason(), Some("OK"));
+        assert_eq!(StatusCode::NOT_FOUND.canonical_reason(), Some("Not Found"));
+        assert_eq!(StatusCode::try_from(299).unwrap().canonical_reason(), None);
+        assert_eq!(StatusCode::NOT_FOUND.to_string(), "404 Not Found");
+        assert_eq!(StatusCode::try_from(299).unwrap().to_string(), "299");
+    }
+
+    #[test]
+    fn test_status_code_classes() {
+        assert!(StatusCode::CONTINUE.is_informational());
+        assert!(StatusCode::NO_CONTENT.is_success());
+        assert!(StatusCode::FOUND.is_redirection());
+        assert!(StatusCode::TOO_MANY_REQUESTS.is_client_error());
+        assert!(!StatusCode::TOO_MANY_REQUESTS.is_server_error());
+        assert!(StatusCode::BAD_GATEWAY.is_server_error());
+        assert!(!StatusCode::BAD_GATEWAY.is_success());
+    }
+
+    #[test]
+    fn test_builder_keeps_repeated_headers() {
+        let response = HttpResponseBuilder::new()
+            .status(StatusCode::OK)
+            .add_header("Set-Cookie".to_owned(), "a=1".to_owned())
+            .add_header("set-cookie".to_owned(), "b=2".to_owned())
+            .add_header("Content-Type".to_owned(), "text/plain".to_owned())
+            .body("hi".to_owned())
+            .build();
+        assert_eq!(response.status_code, StatusCode::OK);
+        assert_eq!(response.headers.get("content-type"), Some("text/plain"));
+        assert_eq!(
+            response.headers.get_all("Set-Cookie").collect::<Vec<_>>(),
+            vec!["a=1", "b=2"]
+        );
+        assert_eq!(response.body, "hi");
+    }
+
+    #[test]
+    fn test_builder_fixed_body() {
+        let response = HttpResponseBuilder::new()
+            .status(StatusCode::NOT_FOUND)
+            .body("nothing here".to_owned())
+            .build();
+        assert_eq!(response.body, "nothing here");
+        assert!(!response.is_chunked());
+        assert!(response.chunks.is_empty());
+        assert!(response.trailers.is_empty());
+    }
+
+    #[test]
+    fn test_builder_chunked_body_with_trailers() {
+        let response = HttpResponseBuilder::new()
+            .status(StatusCode::OK)
+            .add_header("Trailer".to_owned(), "Expires".to_owned())
+            .chunked()
+            .write_chunk("I like ".to_owned())
+            .write_chunk("to eat ".to_owned())
+            .write_chunk("crackers".to_owned())
+            .finish()
+            .add_trailer("Expires".to_owned(), "never".to_owned())
+            .build();
+        assert!(response.is_chunked());
+        assert_eq!(response.headers.get("transfer-encoding"), Some("chunked"));
+        assert_eq!(response.body, "I like to eat crackers");
+        assert_eq!(response.chunks, vec!["I like ", "to eat ", "crackers"]);
+        assert_eq!(response.trailers.get("expires"), Some("never"));
+    }
+
+    #[test]
+    fn test_builder_chunked_body_without_trailers() {
+        let response = HttpResponseBuilder::new()
+            .status(StatusCode::OK)
+            .chunked()
+            .finish()
+            .build();
+        assert!(response.is_chunked());
+        assert_eq!(response.body, "");
+        assert!(response.trailers.is_empty());
+    }
+}
@@ -2,6 +2,11 @@
 #![allow(unused_variables)]
 #![allow(unreachable_code)]
 
+// Worksheets are private unless something outside the crate needs them.
+// `compile_fail` doctests are built as a separate crate, so the worksheets
+// that use them (threads, generics, lifetimes) are public, to show what
+// _doesn't_ compile.
+
 // First let's look at how versatile Rust's "everything is an expression"
 // philosophy is.
 mod expressions;
@@ -10,6 +15,12 @@ mod expressions;
 // It'll be the first real hurdle you'll face.
 mod ownership;
 
+// Smart pointers bend the ownership rules: shared owners, and mutation through `&`.
+mod smart_pointers;
+
+// Ownership is also what makes threads safe.
+pub mod threads;
+
 // Structs are Rust's analog to classes.  Very similar; let's start
 // with structs as a way to group together data.
 mod structs_data;
@@ -19,23 +30,28 @@ mod structs_data;
 mod structs_impl;
 
 // Rust enums are actually Sum types and are incredible powerful.
-mod enums_match;
+// Public so the `worksheet` binary can show color swatches.
+pub mod enums_match;
 
 // Billion-dollar mistake?  Not in Rust!
 mod options;
 
 // Rust's error handling is very powerful, but it takes some getting used to.
-mod errors;
+// Public so the `worksheet` binary can look up error explanations.
+pub mod errors;
+
+// Panics are the other half: unwinding, catching it, and surviving it.
+mod panics;
 
 // Generics are unusually powerful in Rust.
-mod generics;
+pub mod generics;
 
 // Traits are Rust's analog to Interfaces, and remove the need for class hierarchy.
 // They interact powerfully with generics.
 mod traits;
 
 // Lifetimes were never supposed to be something that normal people interacted with. Hah!
-mod lifetimes;
+pub mod lifetimes;
 
 // Using closures successfully in Rust requires understanding them more deeply.
 mod closures;
@@ -25,6 +25,17 @@
 // bug, involving non-determinacy, race-conditions, or multi-
 // threaded data access.
 
+// A zero-copy tokenizer: lifetimes on borrowed data that's actually useful.
+mod tokenizer;
+// Arenas hand out references that all live exactly as long as the arena.
+pub mod arena;
+// Interning strings into an arena, with small `Symbol` ids.
+pub mod interner;
+// A graph whose nodes borrow each other, all from one arena.
+pub mod graph;
+// Drills for the elision rules below, checked by the compiler.
+mod elision;
+
 #[cfg(test)]
 mod tests {
     // FOUNDATIONAL PRINCIPAL: Lifetimes do _not_ affect runtime behavior; they
@@ -48,7 +59,156 @@ mod tests {
         // Doesn't compile!
         // let scoped_num_ref: &'static i32 = &scoped_num;
 
-        // Extra-credit: look up std::mem::leak
+        // `static` items live for the whole program too.  Unlike a const, which is
+        // copied into every place it's used, a static has exactly one address.
+        static GREETING: &str = "hello";
+        let greeting_ref: &'static &str = &GREETING;
+        assert_eq!(*greeting_ref, "hello");
+
+        // You _can_ make a 'static ref to anything, by never freeing it.  Box::leak
+        // gives up ownership, so nothing will ever drop the value.  Useful for
+        // config loaded once at startup; a memory leak anywhere else.
+        let leaked: &'static mut String = Box::leak(Box::new(String::from("forever")));
+        leaked.push('!');
+        assert_eq!(leaked, "forever!");
+    }
+
+    #[test]
+    fn test_lifetimes_static_bounds() {
+        // `T: 'static` does _not_ mean "lives forever".  It means "doesn't borrow
+        // anything that could go away", so owned types like String qualify.
+        fn keep<T: 'static + std::fmt::Debug>(t: T) -> String {
+            format!("{t:?}")
+        }
+        let owned = String::from("owned");
+        assert_eq!(keep(owned), "\"owned\"");
+        assert_eq!(keep("literal"), "\"literal\"");
+        let local = 5;
+        // Doesn't compile: &local borrows something that goes away.
+        // keep(&local);
+
+        // thread::spawn needs a 'static closure, since the thread might outlive
+        // the caller.  `move` makes the closure own what it uses, so it's 'static.
+        let name = String::from("worker");
+        let handle = std::thread::spawn(move || name.len());
+        assert_eq!(handle.join().unwrap(), 6);
+    }
+
+    #[test]
+    fn test_lifetimes_static_trait_objects() {
+        use std::error::Error;
+        use std::fmt;
+
+        // Box<dyn Error> is short for Box<dyn Error + 'static>: the boxed error
+        // can't borrow anything.  That's why it can be passed up any distance.
+        fn parse(s: &str) -> Result<i32, Box<dyn Error>> {
+            Ok(s.trim().parse::<i32>()?)
+        }
+        assert_eq!(parse(" 42 ").unwrap(), 42);
+        assert!(parse("nope").is_err());
+
+        // An error that borrows its input needs a shorter bound, `+ 'a`.
+        #[derive(Debug)]
+        struct BadInput<'a>(&'a str);
+        impl fmt::Display for BadInput<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "bad input `{}`", self.0)
+            }
+        }
+        impl Error for BadInput<'_> {}
+
+        fn check<'a>(s: &'a str) -> Result<(), Box<dyn Error + 'a>> {
+            match s.is_empty() {
+                true => Ok(()),
+                false => Err(Box::new(BadInput(s))),
+            }
+        }
+        let input = String::from("oops");
+        assert_eq!(check(&input).unwrap_err().to_string(), "bad input `oops`");
+    }
+
+    #[test]
+    fn test_lifetimes_static_cow() {
+        use std::borrow::Cow;
+
+        // Cow<'static, str> is either a literal or an owned String.  Common
+        // cases cost nothing, and the rare ones allocate.
+        fn describe(n: u32) -> Cow<'static, str> {
+            match n {
+                0 => Cow::Borrowed("none"),
+                1 => Cow::Borrowed("one"),
+                n => Cow::Owned(format!("{n} of them")),
+            }
+        }
+        assert!(matches!(describe(1), Cow::Borrowed("one")));
+        assert_eq!(describe(3), "3 of them");
+        // Since it's 'static, it can go anywhere, like into another thread.
+        let label = describe(0);
+        assert_eq!(std::thread::spawn(move || label.len()).join().unwrap(), 4);
+    }
+
+    #[test]
+    fn exercise_lifetimes_static_spawn() {
+        let words = ["threads", "need", "static"];
+        unimplemented!(
+            r"
+            EXERCISE: Uncomment the lines below.  They don't compile, because the
+            thread borrows `words`.  Fix it two ways: once by choosing what the
+            closure owns, and once with std::thread::scope.
+        "
+        );
+        // let handle = std::thread::spawn(|| words.len());
+        // assert_eq!(handle.join().unwrap(), 3);
+    }
+
+    #[test]
+    fn exercise_lifetimes_static_bound() {
+        unimplemented!(
+            r"
+            EXERCISE: Uncomment the lines below.  `stash` doesn't compile: Box<dyn
+            Any> needs 'static contents.  Add the bound it needs on T.
+            Then, which of the calls below still don't compile, and why?
+        "
+        );
+        // use std::any::Any;
+        // fn stash<T>(shelf: &mut Vec<Box<dyn Any>>, item: T) {
+        //     shelf.push(Box::new(item));
+        // }
+        // let mut shelf = Vec::new();
+        // let local = String::from("local");
+        // stash(&mut shelf, 1);
+        // stash(&mut shelf, local.clone());
+        // stash(&mut shelf, &local);
+        // assert_eq!(shelf.len(), 3);
+    }
+
+    #[test]
+    fn exercise_lifetimes_static_box_error() {
+        unimplemented!(
+            r"
+            EXERCISE: Uncomment the lines below.  `first_bad` doesn't compile,
+            because its error borrows `inputs` but Box<dyn Error> means 'static.
+            Fix the return type without changing the body.
+        "
+        );
+        // #[derive(Debug)]
+        // struct BadInput<'a>(&'a str);
+        // impl std::fmt::Display for BadInput<'_> {
+        //     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        //         write!(f, "bad input `{}`", self.0)
+        //     }
+        // }
+        // impl std::error::Error for BadInput<'_> {}
+        //
+        // fn first_bad(inputs: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+        //     match inputs.iter().find(|s| s.parse::<i32>().is_err()) {
+        //         Some(bad) => Err(Box::new(BadInput(bad))),
+        //         None => Ok(()),
+        //     }
+        // }
+        // let inputs = vec![String::from("1"), String::from("x")];
+        // let refs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+        // assert_eq!(first_bad(&refs).unwrap_err().to_string(), "bad input `x`");
     }
 
     #[test]
@@ -93,6 +253,7 @@ mod tests {
 
     #[test]
     fn test_lifetimes_elision() {
+        // Each pair below is checked in elision.rs, which also has drills.
         // Elision 1: If there is no lifetime in the return value, the input lifetimes _do not matter_.
         // Because of this, the compiler trivially solves this and elides them.
         fn bar<'a, 'b>(s1: &'a str, s2: &'b str) -> usize {
@@ -0,0 +1,146 @@
+// An arena owns many values and frees them all at once, when it's dropped.
+//
+// `alloc` takes `&self` and returns `&T`, so by elision the value borrows
+// the arena.  That one rule is everything: values can point at each other
+// freely (see graph.rs), since they all die together, and none of them can
+// outlive the arena.
+//
+// Values are kept in chunks that never move once made, each twice as big as
+// the last.  `OnceCell` lets us fill a slot through `&self`.
+
+use std::cell::{Cell, OnceCell};
+
+struct Chunk<T> {
+    slots: Box<[OnceCell<T>]>,
+    next: OnceCell<Box<Chunk<T>>>,
+}
+
+impl<T> Chunk<T> {
+    fn new(size: usize) -> Self {
+        Chunk {
+            slots: (0..size).map(|_| OnceCell::new()).collect(),
+            next: OnceCell::new(),
+        }
+    }
+}
+
+/// Hands out references that live as long as the arena.
+///
+/// A reference can't outlive its arena:
+///
+/// ```compile_fail
+/// use dlcm_rust_workshop::lifetimes::arena::Arena;
+///
+/// let one;
+/// {
+///     let arena = Arena::new();
+///     one = arena.alloc(1);
+/// }
+/// println!("{one}");
+/// ```
+///
+/// And the arena can't move (or be dropped) while anything borrows from it:
+///
+/// ```compile_fail
+/// use dlcm_rust_workshop::lifetimes::arena::Arena;
+///
+/// let arena = Arena::new();
+/// let one = arena.alloc(1);
+/// let moved = arena;
+/// println!("{one}");
+/// ```
+///
+/// But any number of references can be alive at once, while allocating more:
+///
+/// ```
+/// use dlcm_rust_workshop::lifetimes::arena::Arena;
+///
+/// let arena = Arena::new();
+/// let one = arena.alloc(1);
+/// let two = arena.alloc(2);
+/// assert_eq!(one + two, 3);
+/// ```
+pub struct Arena<T> {
+    first: Chunk<T>,
+    len: Cell<usize>,
+}
+
+impl<T> Arena<T> {
+    const FIRST_CHUNK: usize = 8;
+
+    pub fn new() -> Self {
+        Arena {
+            first: Chunk::new(Self::FIRST_CHUNK),
+            len: Cell::new(0),
+        }
+    }
+
+    pub fn alloc(&self, value: T) -> &T {
+        let mut index = self.len.get();
+        self.len.set(index + 1);
+        let mut chunk = &self.first;
+        while index >= chunk.slots.len() {
+            index -= chunk.slots.len();
+            let size = chunk.slots.len() * 2;
+            chunk = chunk.next.get_or_init(|| Box::new(Chunk::new(size)));
+        }
+        // Each slot is only used once, so this always stores `value`.
+        chunk.slots[index].get_or_init(|| value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arena_alloc() {
+        let arena = Arena::new();
+        assert!(arena.is_empty());
+        // Enough to need several chunks.
+        let values: Vec<&String> = (0..100).map(|i| arena.alloc(i.to_string())).collect();
+        assert_eq!(arena.len(), 100);
+        // Early references are still good after the arena grew: chunks never move.
+        assert_eq!(values[0], "0");
+        assert_eq!(values[99], "99");
+    }
+
+    #[test]
+    fn test_arena_values_borrow_the_arena() {
+        fn longest<'a>(arena: &'a Arena<String>, words: &[&str]) -> &'a str {
+            // Each word is copied into the arena, so the result borrows the
+            // arena rather than `words`.
+            let mut longest: &str = "";
+            for word in words {
+                let stored = arena.alloc(word.to_uppercase());
+                if stored.len() > longest.len() {
+                    longest = stored;
+                }
+            }
+            longest
+        }
+
+        let arena = Arena::new();
+        let found = {
+            let words = [String::from("short"), String::from("longest")];
+            let refs: Vec<&str> = words.iter().map(String::as_str).collect();
+            longest(&arena, &refs)
+            // `words` is dropped here, but `found` doesn't borrow it.
+        };
+        assert_eq!(found, "LONGEST");
+    }
+}
@@ -0,0 +1,213 @@
+// A drill for the elision rules: you're given a signature with its
+// lifetimes elided, and write it out with every lifetime explicit.
+//
+// `check_elision!` then has the compiler check your answer.  It declares
+// two traits, one with each signature, and implements each trait with the
+// *other* signature.  An impl's method may be more general than its trait's,
+// but never less, so both impls compile only when the two signatures accept
+// exactly the same callers.  Nothing is ever called: it's all done by the
+// type checker.
+//
+// Equivalent isn't always identical.  With no references in the output,
+// `fn bar<'a>(a: &'a str, b: &'a str) -> usize` passes for
+// `fn bar(a: &str, b: &str) -> usize`: a caller can always shrink both
+// borrows to a common lifetime, so nobody can tell the difference.
+
+/// Checks that an elided signature and an explicit one mean the same thing.
+///
+/// Start with `impl` and the type the methods belong to, or `()` for plain
+/// functions.
+///
+/// ```
+/// struct Foo(String);
+///
+/// dlcm_rust_workshop::check_elision! {
+///     impl Foo;
+///     elided: fn frotz(&self, other: &str) -> &str;
+///     explicit: fn frotz<'a, 'b>(&'a self, other: &'b str) -> &'a str;
+/// }
+/// ```
+///
+/// A wrong answer doesn't compile:
+///
+/// ```compile_fail
+/// struct Foo(String);
+///
+/// dlcm_rust_workshop::check_elision! {
+///     impl Foo;
+///     elided: fn frotz(&self, other: &str) -> &str;
+///     explicit: fn frotz<'a, 'b>(&'a self, other: &'b str) -> &'b str;
+/// }
+/// ```
+///
+/// And neither does one that's right for some callers but not all:
+///
+/// ```compile_fail
+/// struct Foo(String);
+///
+/// dlcm_rust_workshop::check_elision! {
+///     impl Foo;
+///     elided: fn frotz(&self, other: &str) -> &str;
+///     explicit: fn frotz<'a>(&'a self, other: &'a str) -> &'a str;
+/// }
+/// ```
+#[macro_export]
+macro_rules! check_elision {
+    (
+        impl $self_ty:ty;
+        elided: fn $elided:ident $(<$($elided_lt:lifetime),*>)? ($($elided_args:tt)*)
+            $(-> $elided_ret:ty)?;
+        explicit: fn $explicit:ident $(<$($explicit_lt:lifetime),*>)? ($($explicit_args:tt)*)
+            $(-> $explicit_ret:ty)?;
+    ) => {
+        #[allow(dead_code, unused_variables)]
+        const _: () = {
+            trait Elided {
+                fn $elided $(<$($elided_lt),*>)? ($($elided_args)*) $(-> $elided_ret)?;
+            }
+            trait Explicit {
+                fn $explicit $(<$($explicit_lt),*>)? ($($explicit_args)*) $(-> $explicit_ret)?;
+            }
+            // Each impl uses the other trait's signature.
+            impl Elided for $self_ty {
+                fn $elided $(<$($explicit_lt),*>)? ($($explicit_args)*) $(-> $explicit_ret)? {
+                    unreachable!()
+                }
+            }
+            impl Explicit for $self_ty {
+                fn $explicit $(<$($elided_lt),*>)? ($($elided_args)*) $(-> $elided_ret)? {
+                    unreachable!()
+                }
+            }
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    // The signatures from `test_lifetimes_elision`, checked.
+    #[test]
+    fn test_elision_checker() {
+        // Elision 1: no lifetime in the output.
+        check_elision! {
+            impl ();
+            elided: fn bar(s1: &str, s2: &str) -> usize;
+            explicit: fn bar<'a, 'b>(s1: &'a str, s2: &'b str) -> usize;
+        }
+        // Equivalent, as the comment at the top explains.
+        check_elision! {
+            impl ();
+            elided: fn bar(s1: &str, s2: &str) -> usize;
+            explicit: fn bar<'a>(s1: &'a str, s2: &'a str) -> usize;
+        }
+
+        // Elision 2: one input lifetime goes to the output.
+        check_elision! {
+            impl ();
+            elided: fn baz(s1: &str) -> &str;
+            explicit: fn baz<'a>(s1: &'a str) -> &'a str;
+        }
+
+        // Elision 3: inputs that don't reach the output can stay elided.
+        check_elision! {
+            impl ();
+            elided: fn first_str<'a>(s1: &'a str, s2: &str) -> &'a str;
+            explicit: fn first_str<'a, 'b>(s1: &'a str, s2: &'b str) -> &'a str;
+        }
+
+        // Elision 4: with `&self`, the output borrows `self`.
+        // Pro-tip: You can define structs in all sorts of scopes, even inside
+        // a function.
+        struct Foo(String);
+        check_elision! {
+            impl Foo;
+            elided: fn frotz(&self, other: &str) -> &str;
+            explicit: fn frotz<'a, 'b>(&'a self, other: &'b str) -> &'a str;
+        }
+        check_elision! {
+            impl Foo;
+            elided: fn fribble<'b>(&self, other: &'b str) -> &'b str;
+            explicit: fn fribble<'a, 'b>(&'a self, other: &'b str) -> &'b str;
+        }
+
+        // Doesn't compile!  The output borrows `s1`, so it isn't 'static.
+        // check_elision! {
+        //     impl ();
+        //     elided: fn baz(s1: &str) -> &str;
+        //     explicit: fn baz<'a>(s1: &'a str) -> &'static str;
+        // }
+    }
+
+    #[test]
+    fn exercise_elision_drill_functions() {
+        unimplemented!(
+            r"
+            EXERCISE: Uncomment each check below and fill in the explicit
+            signature, with every lifetime named.  Two of the elided
+            signatures don't compile at all; which rule is missing, and what
+            are the different explicit signatures they could mean?
+        "
+        );
+        // check_elision! {
+        //     impl ();
+        //     elided: fn trim(s: &str) -> &str;
+        //     explicit: fn trim<...>(...) -> ...;
+        // }
+        // check_elision! {
+        //     impl ();
+        //     elided: fn split(s: &str, at: usize) -> (&str, &str);
+        //     explicit: fn split<...>(...) -> ...;
+        // }
+        // check_elision! {
+        //     impl ();
+        //     elided: fn count(haystack: &str, needle: &str) -> usize;
+        //     explicit: fn count<...>(...) -> ...;
+        // }
+        // check_elision! {
+        //     impl ();
+        //     elided: fn push(list: &mut Vec<&str>, item: &str);
+        //     explicit: fn push<...>(...);
+        // }
+        // check_elision! {
+        //     impl ();
+        //     elided: fn pick(a: &str, b: &str) -> &str;
+        //     explicit: fn pick<...>(...) -> ...;
+        // }
+        // check_elision! {
+        //     impl ();
+        //     elided: fn first(words: &[&str]) -> &str;
+        //     explicit: fn first<...>(...) -> ...;
+        // }
+    }
+
+    #[test]
+    fn exercise_elision_drill_methods() {
+        struct Parser<'src> {
+            source: &'src str,
+            pos: usize,
+        }
+        unimplemented!(
+            r"
+            EXERCISE: Same again, for methods.  `Parser<'_>` has a lifetime of
+            its own, so which one does `&self` give the output?  For each
+            method, is that the lifetime you'd want?  (Compare with
+            `take_while` in tokenizer.rs.)
+        "
+        );
+        // check_elision! {
+        //     impl Parser<'_>;
+        //     elided: fn rest(&self) -> &str;
+        //     explicit: fn rest<...>(...) -> ...;
+        // }
+        // check_elision! {
+        //     impl Parser<'_>;
+        //     elided: fn advance(&mut self, by: usize) -> &str;
+        //     explicit: fn advance<...>(...) -> ...;
+        // }
+        // check_elision! {
+        //     impl Parser<'_>;
+        //     elided: fn starts_with(&self, prefix: &str) -> bool;
+        //     explicit: fn starts_with<...>(...) -> ...;
+        // }
+    }
+}
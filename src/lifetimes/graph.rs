@@ -0,0 +1,107 @@
+// A graph whose nodes live in an arena and point at each other with plain
+// references.  With owned nodes this would need Rc, Weak and RefCell (see
+// smart_pointers.rs); with an arena, every node lives exactly as long as the
+// arena, so `&'a Node<'a>` is enough, cycles included.
+//
+// Names are interned in a second arena, which has to outlive the nodes' one.
+
+use std::cell::RefCell;
+
+#[derive(Debug)]
+pub struct Node<'a> {
+    pub name: &'a str,
+    // RefCell, since other nodes already borrow this one when we add edges.
+    edges: RefCell<Vec<&'a Node<'a>>>,
+}
+
+impl<'a> Node<'a> {
+    pub fn new(name: &'a str) -> Self {
+        Node {
+            name,
+            edges: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The receiver has to be `&'a self`, not just `&self`: the edge we store
+    /// must live as long as the graph, and so must the node storing it.
+    pub fn connect(&'a self, to: &'a Node<'a>) {
+        self.edges.borrow_mut().push(to);
+    }
+
+    pub fn neighbors(&self) -> Vec<&'a Node<'a>> {
+        self.edges.borrow().clone()
+    }
+}
+
+/// The names of every node reachable from `start`, depth first.
+pub fn reachable<'a>(start: &'a Node<'a>) -> Vec<&'a str> {
+    let mut seen: Vec<&Node> = Vec::new();
+    let mut stack = vec![start];
+    while let Some(node) = stack.pop() {
+        // Compare addresses: two nodes could have the same name.
+        if seen.iter().any(|&other| std::ptr::eq(other, node)) {
+            continue;
+        }
+        seen.push(node);
+        stack.extend(node.neighbors().into_iter().rev());
+    }
+    seen.into_iter().map(|node| node.name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::arena::Arena;
+    use super::super::interner::{Interner, Symbol};
+    use super::*;
+
+    #[test]
+    fn test_graph_with_cycle() {
+        // Declared first, so dropped last: the names outlive the nodes.
+        let names = Arena::new();
+        let mut interner = Interner::new(&names);
+        let nodes = Arena::new();
+        let mut node = |name: &str| nodes.alloc(Node::new(interner.intern_str(name)));
+        let a = node("a");
+        let b = node("b");
+        let c = node("c");
+        let lonely = node("lonely");
+
+        a.connect(b);
+        b.connect(c);
+        // A cycle: fine, since nothing here owns anything.
+        c.connect(a);
+        c.connect(b);
+
+        assert_eq!(reachable(a), ["a", "b", "c"]);
+        assert_eq!(reachable(c), ["c", "a", "b"]);
+        assert_eq!(reachable(lonely), ["lonely"]);
+        assert_eq!(nodes.len(), 4);
+    }
+
+    #[test]
+    fn test_graph_symbols_outlive_nodes() {
+        let names = Arena::new();
+        let mut interner = Interner::new(&names);
+        let found: Vec<Symbol> = {
+            let nodes = Arena::new();
+            let start = nodes.alloc(Node::new(interner.intern_str("start")));
+            let end = nodes.alloc(Node::new(interner.intern_str("end")));
+            start.connect(end);
+            // `Node<'a>` is invariant in 'a (it's inside a RefCell), so these
+            // names are stuck with the nodes' lifetime, and can't leave the
+            // block.  Symbols borrow nothing, so they can.
+            reachable(start)
+                .into_iter()
+                .map(|name| interner.get(name).unwrap())
+                .collect()
+        };
+        let found: Vec<&str> = found.into_iter().map(|s| interner.resolve(s)).collect();
+        assert_eq!(found, ["start", "end"]);
+
+        // Doesn't compile!  The nodes' arena must outlive the edges into it.
+        // let dangling = {
+        //     let nodes = Arena::new();
+        //     nodes.alloc(Node::new("gone"))
+        // };
+    }
+}
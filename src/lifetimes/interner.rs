@@ -0,0 +1,137 @@
+// Interning keeps one copy of each distinct string, and gives each one a
+// small `Symbol` that's cheap to copy, compare and hash.
+//
+// The strings live in an `Arena` that outlives the `Interner`, so the
+// `&'a str`s it hands out borrow the arena, not the interner.  That's why
+// they can still be used while `intern` (which takes `&mut self`) adds more.
+
+use std::collections::HashMap;
+
+use super::arena::Arena;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+/// Interns strings into an arena.
+///
+/// Interned strings can't outlive the arena, even if the interner is gone:
+///
+/// ```compile_fail
+/// use dlcm_rust_workshop::lifetimes::arena::Arena;
+/// use dlcm_rust_workshop::lifetimes::interner::Interner;
+///
+/// let name;
+/// {
+///     let arena = Arena::new();
+///     let mut interner = Interner::new(&arena);
+///     name = interner.intern_str("gone");
+/// }
+/// println!("{name}");
+/// ```
+///
+/// But they can outlive the interner:
+///
+/// ```
+/// use dlcm_rust_workshop::lifetimes::arena::Arena;
+/// use dlcm_rust_workshop::lifetimes::interner::Interner;
+///
+/// let arena = Arena::new();
+/// let name = {
+///     let mut interner = Interner::new(&arena);
+///     interner.intern_str("kept")
+/// };
+/// assert_eq!(name, "kept");
+/// ```
+pub struct Interner<'a> {
+    arena: &'a Arena<String>,
+    symbols: HashMap<&'a str, Symbol>,
+    strings: Vec<&'a str>,
+}
+
+impl<'a> Interner<'a> {
+    pub fn new(arena: &'a Arena<String>) -> Self {
+        Interner {
+            arena,
+            symbols: HashMap::new(),
+            strings: Vec::new(),
+        }
+    }
+
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(s) {
+            return symbol;
+        }
+        let stored: &'a str = self.arena.alloc(s.to_owned());
+        let symbol = Symbol(self.strings.len() as u32);
+        self.symbols.insert(stored, symbol);
+        self.strings.push(stored);
+        symbol
+    }
+
+    /// The one stored copy of `s`.
+    pub fn intern_str(&mut self, s: &str) -> &'a str {
+        let symbol = self.intern(s);
+        self.resolve(symbol)
+    }
+
+    /// The symbol for `s`, if it's been interned.
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.symbols.get(s).copied()
+    }
+
+    /// A `Symbol` is just an index, so one from a different interner may
+    /// return the wrong string, or panic.
+    pub fn resolve(&self, symbol: Symbol) -> &'a str {
+        self.strings[symbol.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interner_dedups() {
+        let arena = Arena::new();
+        let mut interner = Interner::new(&arena);
+        let apple = interner.intern("apple");
+        let pear = interner.intern("pear");
+        // Built at runtime, so it's a different String with the same contents.
+        let apple_again = interner.intern(&format!("app{}", "le"));
+        assert_eq!(apple, apple_again);
+        assert_ne!(apple, pear);
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.get("pear"), Some(pear));
+        assert_eq!(interner.get("plum"), None);
+        assert_eq!(interner.resolve(pear), "pear");
+
+        // Nothing stops a symbol from being used with the wrong interner.
+        let other_arena = Arena::new();
+        let mut other = Interner::new(&other_arena);
+        other.intern("plum");
+        assert_eq!(other.resolve(apple), "plum");
+    }
+
+    #[test]
+    fn test_interner_strs_borrow_the_arena() {
+        let arena = Arena::new();
+        let mut interner = Interner::new(&arena);
+        // Holding on to `first` while calling `intern` (a `&mut self` method)
+        // is fine: `first` borrows the arena, not the interner.
+        let first = interner.intern_str("shared");
+        let second = interner.intern_str("shared");
+        interner.intern("other");
+        // One copy, so they're the very same bytes.
+        assert!(std::ptr::eq(first, second));
+        drop(interner);
+        assert_eq!(first, "shared");
+    }
+}
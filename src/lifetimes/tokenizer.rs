@@ -0,0 +1,257 @@
+// A tokenizer that never copies: every token is a slice of the source text.
+//
+// This is where lifetimes earn their keep.  `Token<'a>` borrows from the
+// source `&'a str`, *not* from the `Tokenizer`, so tokens can outlive the
+// tokenizer that found them.  All of that is in the one signature:
+//
+//     impl<'a> Iterator for Tokenizer<'a> {
+//         type Item = Result<Token<'a>, TokenizeError>;
+//
+// Lines and columns are 1-based, and columns count characters, not bytes.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Letters, digits and `_`, not starting with a digit.
+    Word,
+    /// Digits, with an optional fractional part: `42`, `3.14`.
+    Number,
+    /// A double-quoted string; the token's text is what's between the quotes.
+    /// There are no escapes, since those would need a new `String`.
+    Str,
+    /// Any other single character, like `+` or `(`.
+    Punct,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub line: u32,
+    pub col: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unterminated string starting at line {line}, col {col}")]
+pub struct TokenizeError {
+    pub line: u32,
+    pub col: u32,
+}
+
+pub struct Tokenizer<'a> {
+    source: &'a str,
+    /// Byte offset of the next character.
+    pos: usize,
+    line: u32,
+    col: u32,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(source: &'a str) -> Self {
+        Tokenizer {
+            source,
+            pos: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    // The return type says the slice borrows from the source ('a), not from
+    // `self`.  With the elided `&str`, it would borrow `self` instead, and
+    // we couldn't call `bump` again while holding on to it.
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&pred) {
+            self.bump();
+        }
+        &self.source[start..self.pos]
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token<'a>, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.take_while(char::is_whitespace);
+        let (line, col) = (self.line, self.col);
+        let token = |kind, text| {
+            Some(Ok(Token {
+                kind,
+                text,
+                line,
+                col,
+            }))
+        };
+
+        let start = self.pos;
+        match self.peek()? {
+            c if c.is_alphabetic() || c == '_' => {
+                let text = self.take_while(|c| c.is_alphanumeric() || c == '_');
+                token(TokenKind::Word, text)
+            }
+            c if c.is_ascii_digit() => {
+                self.take_while(|c| c.is_ascii_digit());
+                // Only take the `.` if a digit follows, so `1.` is `1` then `.`.
+                let rest = &self.source[self.pos..];
+                if rest.starts_with('.') && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+                    self.bump();
+                    self.take_while(|c| c.is_ascii_digit());
+                }
+                token(TokenKind::Number, &self.source[start..self.pos])
+            }
+            '"' => {
+                self.bump();
+                let text = self.take_while(|c| c != '"');
+                if self.bump().is_none() {
+                    return Some(Err(TokenizeError { line, col }));
+                }
+                token(TokenKind::Str, text)
+            }
+            _ => {
+                self.bump();
+                token(TokenKind::Punct, &self.source[start..self.pos])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(source: &str) -> Vec<Token<'_>> {
+        Tokenizer::new(source).map(Result::unwrap).collect()
+    }
+
+    fn texts(source: &str) -> Vec<(TokenKind, &str)> {
+        tokens(source)
+            .into_iter()
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenizer_kinds() {
+        use TokenKind::*;
+        assert_eq!(
+            texts(r#"let x_1 = 3.14 * (radius + 2.) + "in ""#),
+            [
+                (Word, "let"),
+                (Word, "x_1"),
+                (Punct, "="),
+                (Number, "3.14"),
+                (Punct, "*"),
+                (Punct, "("),
+                (Word, "radius"),
+                (Punct, "+"),
+                (Number, "2"),
+                (Punct, "."),
+                (Punct, ")"),
+                (Punct, "+"),
+                (Str, "in "),
+            ]
+        );
+        assert_eq!(texts("  \n\t "), []);
+    }
+
+    #[test]
+    fn test_tokenizer_positions() {
+        let positions: Vec<(&str, u32, u32)> = tokens("fn main() {\n    née(\"é\", 1)\n}")
+            .into_iter()
+            .map(|token| (token.text, token.line, token.col))
+            .collect();
+        // Columns count characters, so the accented letters take one column.
+        assert_eq!(
+            positions,
+            [
+                ("fn", 1, 1),
+                ("main", 1, 4),
+                ("(", 1, 8),
+                (")", 1, 9),
+                ("{", 1, 11),
+                ("née", 2, 5),
+                ("(", 2, 8),
+                ("é", 2, 9),
+                (",", 2, 12),
+                ("1", 2, 14),
+                (")", 2, 15),
+                ("}", 3, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_zero_copy() {
+        let source = String::from("alpha \"beta\" 42");
+        let range = source.as_bytes().as_ptr_range();
+        for token in tokens(&source) {
+            // Each token points into `source` itself; nothing was copied.
+            assert!(range.contains(&token.text.as_ptr()), "{token:?}");
+        }
+    }
+
+    #[test]
+    fn test_tokenizer_tokens_outlive_tokenizer() {
+        let source = String::from("keep these words");
+        let kept: Vec<Token> = {
+            let mut tokenizer = Tokenizer::new(&source);
+            let first = tokenizer.next().unwrap().unwrap();
+            let rest: Vec<Token> = tokenizer.by_ref().map(Result::unwrap).collect();
+            // The tokenizer is dropped at the end of this block, but the
+            // tokens only borrow `source`, so they can leave with us.
+            [vec![first], rest].concat()
+        };
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[2].text, "words");
+
+        // But they can't outlive the source.  Doesn't compile!
+        // let dangling = {
+        //     let source = String::from("gone");
+        //     tokens(&source)
+        // };
+    }
+
+    #[test]
+    fn test_tokenizer_unterminated_string() {
+        let mut tokenizer = Tokenizer::new("say \"hello\nworld");
+        assert_eq!(tokenizer.next().unwrap().unwrap().text, "say");
+        let err = tokenizer.next().unwrap().unwrap_err();
+        assert_eq!(err, TokenizeError { line: 1, col: 5 });
+        assert_eq!(
+            err.to_string(),
+            "unterminated string starting at line 1, col 5"
+        );
+        // The string ate the rest of the input.
+        assert_eq!(tokenizer.next(), None);
+    }
+
+    #[test]
+    fn exercise_lifetimes_tokenizer_words() {
+        let text = String::from("the quick (brown) fox, 42 times");
+        unimplemented!(
+            r"
+            EXERCISE: Write `fn words(text: &str) -> Vec<&str>` that returns
+            only the Word tokens.  Does it need any lifetime annotations?
+            Now write `fn longest_word(a: &str, b: &str) -> Option<&str>` that
+            returns the longest word in either.  Why does it need them?
+        "
+        );
+        // assert_eq!(words(&text), ["the", "quick", "brown", "fox", "times"]);
+        // assert_eq!(longest_word(&text, "jumps"), Some("quick"));
+    }
+}
@@ -1,13 +1,28 @@
 // We'll need a basic struct for ownership.  We'll get into them more soon.
 
+// Tracked<T> logs when values are created, cloned and dropped, so the tests
+// can check what happens instead of just claiming it in comments.
+mod tracked;
+
 // derive macros let us give canonical behavior easily.  Ignore until we see traits.
+// pub(crate) so later worksheets (threads.rs, smart_pointers.rs) can use them too.
 #[derive(Debug)]
-struct Foo(i32);
+pub(crate) struct Foo(pub(crate) i32);
+
+// Unlike Foo, Bar can be cloned.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Bar(pub(crate) String);
 
 #[cfg(test)]
 mod tests {
-    // Make it easy to refer to Foo.
-    use super::Foo;
+    // Make it easy to refer to Foo and Bar.
+    use super::tracked::{self, Tracked};
+    use super::{Bar, Foo};
+
+    // The journal as strings, for easy comparison.
+    fn trace(f: impl FnOnce()) -> Vec<String> {
+        tracked::record(f).iter().map(ToString::to_string).collect()
+    }
 
     #[test]
     fn test_ownership_move_semantics() {
@@ -34,6 +49,33 @@ mod tests {
         // println!("test_pass_by_move {x:?}");
     }
 
+    #[test]
+    fn test_ownership_moves_tracked() {
+        let events = trace(|| {
+            let x = Tracked::new("x", Foo(0));
+            // Moving doesn't copy or drop anything...
+            let y = x;
+            tracked::note("moved x to y");
+            // ...but now it's y that drops the value, when y goes out of scope.
+            pass_by_move_tracked(y);
+            tracked::note("after pass_by_move");
+        });
+        assert_eq!(
+            events,
+            [
+                "new x",
+                "-- moved x to y",
+                "-- in pass_by_move",
+                "drop x",
+                "-- after pass_by_move"
+            ]
+        );
+    }
+
+    fn pass_by_move_tracked(foo: Tracked<Foo>) {
+        tracked::note("in pass_by_move");
+    }
+
     fn pass_by_ref(foo: &Foo) {
         println!("pass_by_ref {foo:?}");
     }
@@ -48,6 +90,18 @@ mod tests {
         println!("test_pass_by_ref y {y:?}");
     }
 
+    #[test]
+    fn test_ownership_borrows_tracked() {
+        let events = trace(|| {
+            let x = Tracked::new("x", Foo(0));
+            // Borrowing lends the value out; it comes back untouched.
+            pass_by_ref(&x);
+            let y = &x;
+            tracked::note("borrowed");
+        });
+        assert_eq!(events, ["new x", "-- borrowed", "drop x"]);
+    }
+
     fn pass_by_mut(foo: &mut Foo) {
         // This accesses the 0th element; we'll dig into this next module.
         foo.0 += 1;
@@ -81,6 +135,56 @@ mod tests {
         // EXERCISE: do this with std::mem::swap or std::mem::replace
     }
 
+    #[test]
+    fn test_ownership_swap_tracked() {
+        let events = trace(|| {
+            let mut x = Tracked::new("zero", Foo(0));
+            let y = &mut x;
+            // Assigning through a &mut drops the old value straight away.
+            *y = Tracked::new("one", Foo(1));
+            tracked::note("assigned");
+            let mut z = Tracked::new("two", Foo(2));
+            // Swapping doesn't drop (or create) anything.
+            std::mem::swap(&mut x, &mut z);
+            tracked::note("swapped");
+            assert_eq!((x.0, z.0), (2, 1));
+        });
+        // z was declared last so it's dropped first, and holds "one" now.
+        assert_eq!(
+            events,
+            [
+                "new zero",
+                "new one",
+                "drop zero",
+                "-- assigned",
+                "new two",
+                "-- swapped",
+                "drop one",
+                "drop two"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ownership_shadowing_tracked() {
+        let events = trace(|| {
+            let x = Tracked::new("first", Foo(0));
+            // Shadowing hides the first x, but doesn't drop it.
+            let x = Tracked::new("second", Foo(1));
+            tracked::note("shadowed");
+        });
+        assert_eq!(
+            events,
+            [
+                "new first",
+                "new second",
+                "-- shadowed",
+                "drop second",
+                "drop first"
+            ]
+        );
+    }
+
     #[test]
     fn test_ownership_strings() {
         // Rust is a bit persnickity about different string types.
@@ -104,10 +208,6 @@ mod tests {
         assert_eq!(s, "different literal!");
     }
 
-    // Pro-tip: You can define structs in all sorts of scopes.
-    #[derive(Debug, Clone)]
-    struct Bar(String);
-
     #[test]
     fn test_ownership_clone() {
         // You can make things simpler in a lot of cases by cloning an object.  If the object
@@ -120,6 +220,21 @@ mod tests {
         assert_eq!(bar2.0, "please change me!");
     }
 
+    #[test]
+    fn test_ownership_clone_tracked() {
+        let events = trace(|| {
+            let bar = Tracked::new("bar", Bar(String::from("don't change me!")));
+            let mut bar2 = bar.clone();
+            bar2.0.replace_range(0..5, "please");
+            assert_eq!(bar.0, "don't change me!");
+        });
+        // Two separate values, each dropped on its own.
+        assert_eq!(
+            events,
+            ["new bar", "clone bar -> bar'", "drop bar'", "drop bar"]
+        );
+    }
+
     #[test]
     fn exercise_ownership_move() {
         let bar = Bar(String::from("I like to eat crackers"));
@@ -177,5 +292,5 @@ mod tests {
         );
     }
 
-    // TODO: Ownership and multi-threading
+    // Ownership and multi-threading continue in threads.rs.
 }
@@ -0,0 +1,142 @@
+// `Tracked<T>` wraps a value and writes down when it's created, cloned and
+// dropped, so tests can check what the comments in ownership.rs claim.
+//
+// Moves don't run any code, so they never show up in the journal.  That's the
+// point: a move just changes who will drop the value, which shows up as *when*
+// it's dropped.
+
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    New(String),
+    Clone {
+        from: String,
+        to: String,
+    },
+    Drop(String),
+    /// A marker from `note`, to show where in the test things happened.
+    Note(String),
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::New(name) => write!(f, "new {name}"),
+            Event::Clone { from, to } => write!(f, "clone {from} -> {to}"),
+            Event::Drop(name) => write!(f, "drop {name}"),
+            Event::Note(note) => write!(f, "-- {note}"),
+        }
+    }
+}
+
+thread_local! {
+    // One journal per thread, so tests running in parallel don't mix events.
+    static JOURNAL: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+}
+
+fn log(event: Event) {
+    JOURNAL.with(|journal| journal.borrow_mut().push(event));
+}
+
+/// Add a marker to the journal.
+pub fn note(note: &str) {
+    log(Event::Note(note.to_owned()));
+}
+
+/// Run `f`, and return everything it put in this thread's journal.
+pub fn record(f: impl FnOnce()) -> Vec<Event> {
+    JOURNAL.with(|journal| journal.borrow_mut().clear());
+    f();
+    JOURNAL.with(|journal| journal.take())
+}
+
+#[derive(Debug)]
+pub struct Tracked<T> {
+    name: String,
+    value: T,
+}
+
+impl<T> Tracked<T> {
+    pub fn new(name: &str, value: T) -> Self {
+        log(Event::New(name.to_owned()));
+        Tracked {
+            name: name.to_owned(),
+            value,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Clones are named after the original, with a `'`.
+impl<T: Clone> Clone for Tracked<T> {
+    fn clone(&self) -> Self {
+        let name = format!("{}'", self.name);
+        log(Event::Clone {
+            from: self.name.clone(),
+            to: name.clone(),
+        });
+        Tracked {
+            name,
+            value: self.value.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        log(Event::Drop(self.name.clone()));
+    }
+}
+
+// Deref lets a Tracked<Foo> be used like a Foo: `tracked.0` works.
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracked_journal() {
+        let events = record(|| {
+            let a = Tracked::new("a", vec![1]);
+            let mut b = a.clone();
+            b.push(2);
+            note("changed b");
+            assert_eq!((a.len(), b.len(), b.name()), (1, 2, "a'"));
+        });
+        // Locals are dropped in reverse order of declaration.
+        assert_eq!(
+            events,
+            [
+                Event::New("a".to_owned()),
+                Event::Clone {
+                    from: "a".to_owned(),
+                    to: "a'".to_owned()
+                },
+                Event::Note("changed b".to_owned()),
+                Event::Drop("a'".to_owned()),
+                Event::Drop("a".to_owned()),
+            ]
+        );
+        assert_eq!(events[1].to_string(), "clone a -> a'");
+        // Recording again starts from a clean journal.
+        assert_eq!(record(|| ()), []);
+    }
+}
@@ -0,0 +1,285 @@
+/*
+A panic unwinds the stack of the current thread: each frame is popped and
+its values dropped, until the panic reaches the top of the thread and the
+thread dies.  If that thread is `main`, the process exits.
+
+Unwinding can be stopped partway up with `std::panic::catch_unwind`.  This is
+NOT exception handling, and shouldn't be used like it; `Result` is for errors
+you expect.  It's for the boundaries of a program: a thread pool that wants
+to survive a bad task, a test harness (that's how `#[should_panic]` works),
+or an `extern "C"` function that must never unwind into C code.
+
+A panic carries a "payload", which is almost always the message: a `&'static
+str` for `panic!("literal")`, or a `String` for `panic!("formatted {x}")`.
+*/
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::panic::{self, UnwindSafe};
+use std::sync::Once;
+
+/// Get the message out of a panic payload, if it has one.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&'static str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "<non-string panic payload>".to_owned()
+    }
+}
+
+thread_local! {
+    // `Some` while `capture_panics` is running on this thread.
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+/// Run `f`, catching any panic, and return the messages of every panic that
+/// happened on this thread while it ran (including ones that `f` caught
+/// itself).  Captured panics aren't printed.
+///
+/// The panic hook is process-wide, so the hook installed here only captures
+/// on threads that asked for it, and defers to the previous hook otherwise.
+pub fn capture_panics<R>(
+    f: impl FnOnce() -> R + UnwindSafe,
+) -> (std::thread::Result<R>, Vec<String>) {
+    INSTALL_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let captured = CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+                Some(messages) => {
+                    messages.push(panic_message(info.payload()));
+                    true
+                }
+                None => false,
+            });
+            if !captured {
+                previous(info);
+            }
+        }));
+    });
+
+    CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+    let result = panic::catch_unwind(f);
+    let messages = CAPTURED.with(|captured| captured.borrow_mut().take().unwrap_or_default());
+    (result, messages)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SupervisorError {
+    GaveUp { panics: Vec<String> },
+}
+
+/// Runs a worker on its own thread, restarting it if it panics, in the style
+/// of an Erlang supervisor.  Each attempt gets a fresh thread, so a panic
+/// can't leave half-updated thread-local state behind for the next one.
+pub struct Supervisor {
+    pub max_restarts: u32,
+}
+
+impl Supervisor {
+    /// Run `worker` until it returns.  It's passed the attempt number,
+    /// starting from 0.  Returns the worker's result, and the messages of the
+    /// panics it took to get there.
+    pub fn run<T, F>(&self, worker: F) -> Result<(T, Vec<String>), SupervisorError>
+    where
+        T: Send,
+        F: Fn(u32) -> T + Sync,
+    {
+        let mut panics = Vec::new();
+        for attempt in 0..=self.max_restarts {
+            // A scoped thread can borrow `worker`, so it needn't be 'static.
+            let result = std::thread::scope(|s| s.spawn(|| worker(attempt)).join());
+            match result {
+                Ok(value) => return Ok((value, panics)),
+                Err(payload) => panics.push(panic_message(payload.as_ref())),
+            }
+        }
+        Err(SupervisorError::GaveUp { panics })
+    }
+}
+
+/// A function C code can call.  Unwinding out of an `extern "C"` function
+/// aborts the process, so catch the panic and turn it into an error code.
+pub extern "C" fn checked_percent(part: i32, whole: i32) -> i32 {
+    panic::catch_unwind(|| part * 100 / whole).unwrap_or(-1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::AssertUnwindSafe;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_panics_catch_unwind() {
+        let ok = panic::catch_unwind(|| 1);
+        assert_eq!(ok.unwrap(), 1);
+
+        // The Err holds the payload.  Note the two payload types.
+        let literal = panic::catch_unwind(|| panic!("boom")).unwrap_err();
+        assert!(literal.is::<&'static str>());
+        assert_eq!(panic_message(literal.as_ref()), "boom");
+
+        let x = 42;
+        let formatted = panic::catch_unwind(|| panic!("boom {x}")).unwrap_err();
+        assert!(formatted.is::<String>());
+        assert_eq!(panic_message(formatted.as_ref()), "boom 42");
+
+        // Anything can be a payload, with `panic_any`.
+        let weird = panic::catch_unwind(|| std::panic::panic_any(7_u8)).unwrap_err();
+        assert_eq!(panic_message(weird.as_ref()), "<non-string panic payload>");
+    }
+
+    #[test]
+    fn test_panics_unwinding_drops_values() {
+        struct SetOnDrop<'a>(&'a AtomicU32);
+        impl Drop for SetOnDrop<'_> {
+            fn drop(&mut self) {
+                self.0.store(1, Ordering::SeqCst);
+            }
+        }
+
+        // Unwinding runs destructors on the way up, just like a normal return.
+        let dropped = AtomicU32::new(0);
+        let result = panic::catch_unwind(|| {
+            let _guard = SetOnDrop(&dropped);
+            panic!("unwinding");
+        });
+        assert!(result.is_err());
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_panics_thread_join() {
+        // A panic kills its own thread, not the process.  `join` tells you.
+        let handle = std::thread::spawn(|| -> i32 { panic!("worker died") });
+        let payload = handle.join().unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "worker died");
+
+        let handle = std::thread::spawn(|| 6 * 7);
+        assert_eq!(handle.join().unwrap(), 42);
+    }
+
+    #[test]
+    fn test_panics_hook_captures_messages() {
+        let (result, messages) = capture_panics(|| {
+            // Caught inside, but the hook still saw it.
+            let _ = panic::catch_unwind(|| panic!("first"));
+            let n = 2;
+            panic!("second, with {n}");
+        });
+        assert!(result.is_err());
+        assert_eq!(messages, vec!["first", "second, with 2"]);
+
+        let (result, messages) = capture_panics(|| "fine");
+        assert_eq!(result.unwrap(), "fine");
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn test_panics_hook_is_per_thread() {
+        let (_, messages) = capture_panics(|| {
+            // This panic is on another thread, which isn't capturing.
+            let _ = std::thread::spawn(|| panic!("elsewhere")).join();
+        });
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn test_panics_unwind_safe_pitfall() {
+        // `catch_unwind` only takes `UnwindSafe` closures.  A closure holding a
+        // `&mut` isn't, because after a panic the data may be half-updated.
+        // `AssertUnwindSafe` says "I've thought about it", but see what happens:
+        let mut sorted = vec![1, 2, 3];
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            sorted.push(0);
+            // ...we meant to sort next, but panicked first.
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        // The invariant ("sorted is sorted") is now broken.
+        assert_eq!(sorted, vec![1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn test_panics_mutex_poisoning() {
+        // A Mutex is how the standard library protects against the pitfall
+        // above across threads: panicking while holding the lock "poisons" it.
+        let data = Arc::new(Mutex::new(vec![1, 2, 3]));
+        let thread_data = Arc::clone(&data);
+        let _ = std::thread::spawn(move || {
+            let mut guard = thread_data.lock().unwrap();
+            guard.push(0);
+            panic!("while holding the lock");
+        })
+        .join();
+
+        assert!(data.is_poisoned());
+        // Later lockers get an error, but can still get at the data if they
+        // decide it's fine.
+        let recovered = data.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        assert_eq!(*recovered, vec![1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn test_panics_supervisor_restarts() {
+        let starts = AtomicU32::new(0);
+        let supervisor = Supervisor { max_restarts: 3 };
+        let result = supervisor.run(|attempt| {
+            starts.fetch_add(1, Ordering::SeqCst);
+            if attempt < 2 {
+                panic!("crash {attempt}");
+            }
+            attempt * 10
+        });
+        assert_eq!(
+            result,
+            Ok((20, vec!["crash 0".to_owned(), "crash 1".to_owned()]))
+        );
+        assert_eq!(starts.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_panics_supervisor_gives_up() {
+        let supervisor = Supervisor { max_restarts: 2 };
+        let result: Result<((), _), _> = supervisor.run(|_| panic!("always"));
+        assert_eq!(
+            result,
+            Err(SupervisorError::GaveUp {
+                panics: vec!["always".to_owned(); 3]
+            })
+        );
+    }
+
+    #[test]
+    fn test_panics_ffi_boundary() {
+        assert_eq!(checked_percent(1, 4), 25);
+        // Division by zero panics, but the caller just sees -1.
+        assert_eq!(checked_percent(1, 0), -1);
+    }
+
+    #[test]
+    fn exercise_panics_unwind_safe() {
+        unimplemented!(
+            r"
+            The lines below don't compile: the closure captures `count` by `&mut`,
+            which isn't `UnwindSafe`.  Make them compile.  Then make the assert
+            pass by choosing the right value.  Is `count` in a sensible state?
+            "
+        );
+
+        // let mut count = 0;
+        // let result = panic::catch_unwind(|| {
+        //     count += 1;
+        //     if count > 0 {
+        //         panic!("too many");
+        //     }
+        // });
+        // assert!(result.is_err());
+        // assert_eq!(count, ???);
+    }
+}
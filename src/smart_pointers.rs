@@ -0,0 +1,256 @@
+// Ownership says every value has one owner, and `&mut` means nobody else is
+// looking.  Smart pointers bend those rules, safely:
+//
+//     Box<T>:      one owner, but the value lives on the heap.
+//     Rc<T>:       many owners; the value is dropped with the last one.
+//     Weak<T>:     a pointer that doesn't own, so it can't keep a value alive.
+//     Cell<T>, RefCell<T>: change a value through a `&`.  RefCell checks the
+//                  borrow rules while the program runs, instead of at compile time.
+//     Cow<T>:      borrowed until you need to change it, then owned.
+//
+// None of these are thread-safe; threads.rs has their counterparts.
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+// We'll reuse Foo from the ownership module.
+use crate::ownership::Foo;
+
+// A list is either empty, or an item followed by a list.  Without the Box,
+// List would contain a List, which contains a List... and have infinite size.
+// A Box is always the size of a pointer, whatever it points to.
+#[derive(Debug)]
+enum List {
+    Cons(Foo, Box<List>),
+    Nil,
+}
+
+impl List {
+    fn from_values(values: &[i32]) -> List {
+        values.iter().rev().fold(List::Nil, |list, &value| {
+            List::Cons(Foo(value), Box::new(list))
+        })
+    }
+
+    fn sum(&self) -> i32 {
+        match self {
+            // Box derefs to what it holds, so `rest` can call List methods.
+            List::Cons(Foo(value), rest) => value + rest.sum(),
+            List::Nil => 0,
+        }
+    }
+}
+
+// A tree where children can find their parent.  Parents own their children
+// (Rc), but children only point back (Weak).  If both were Rc, parent and
+// child would keep each other alive forever: a leak.
+#[derive(Debug)]
+struct TreeNode {
+    foo: Foo,
+    // RefCell, since we set these after the node is already shared.
+    parent: RefCell<Weak<TreeNode>>,
+    children: RefCell<Vec<Rc<TreeNode>>>,
+}
+
+impl TreeNode {
+    fn new(value: i32) -> Rc<TreeNode> {
+        Rc::new(TreeNode {
+            foo: Foo(value),
+            parent: RefCell::new(Weak::new()),
+            children: RefCell::new(Vec::new()),
+        })
+    }
+
+    fn add_child(parent: &Rc<TreeNode>, child: Rc<TreeNode>) {
+        *child.parent.borrow_mut() = Rc::downgrade(parent);
+        parent.children.borrow_mut().push(child);
+    }
+
+    fn parent_value(&self) -> Option<i32> {
+        // upgrade() gives an Rc if the parent is still alive.
+        self.parent.borrow().upgrade().map(|parent| parent.foo.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ownership::Bar;
+    use std::borrow::Cow;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_smart_pointers_box() {
+        let boxed = Box::new(Foo(1));
+        // Box<Foo> works (almost) everywhere a Foo does.
+        assert_eq!(boxed.0, 1);
+        // * moves the Foo out of the box, back onto the stack.
+        let foo: Foo = *boxed;
+        assert_eq!(foo.0, 1);
+
+        let list = List::from_values(&[1, 2, 3]);
+        assert_eq!(list.sum(), 6);
+        assert!(matches!(list, List::Cons(Foo(1), _)));
+    }
+
+    #[test]
+    fn test_smart_pointers_rc() {
+        let bar = Rc::new(Bar(String::from("shared")));
+        assert_eq!(Rc::strong_count(&bar), 1);
+        {
+            // Rc::clone copies the pointer, not the Bar.
+            let also_bar = Rc::clone(&bar);
+            assert!(Rc::ptr_eq(&bar, &also_bar));
+            assert_eq!(Rc::strong_count(&bar), 2);
+        }
+        // also_bar was dropped at the end of its block.
+        assert_eq!(Rc::strong_count(&bar), 1);
+        // Rc only hands out & access, since others might be looking.
+        // bar.0.push('!');
+        assert_eq!(bar.0, "shared");
+    }
+
+    #[test]
+    fn test_smart_pointers_tree_weak() {
+        let leaf = TreeNode::new(3);
+        assert_eq!(leaf.parent_value(), None);
+        {
+            let branch = TreeNode::new(5);
+            TreeNode::add_child(&branch, Rc::clone(&leaf));
+            assert_eq!(leaf.parent_value(), Some(5));
+            assert_eq!(branch.children.borrow()[0].foo.0, 3);
+
+            // The leaf is owned here and by the branch; the branch is owned
+            // only here, with one Weak pointer from the leaf.
+            assert_eq!(Rc::strong_count(&leaf), 2);
+            assert_eq!(Rc::strong_count(&branch), 1);
+            assert_eq!(Rc::weak_count(&branch), 1);
+        }
+        // The Weak didn't keep the branch alive, and the branch's Rc to the
+        // leaf went with it.  Nothing leaked.
+        assert_eq!(leaf.parent_value(), None);
+        assert_eq!(Rc::strong_count(&leaf), 1);
+    }
+
+    #[test]
+    fn test_smart_pointers_cell() {
+        // Cell swaps values in and out, so it never hands out references.
+        // That makes it cheap, but it only works well for Copy types.
+        let count = Cell::new(0);
+        let bump = || count.set(count.get() + 1);
+        bump();
+        bump();
+        assert_eq!(count.get(), 2);
+        assert_eq!(count.replace(10), 2);
+        assert_eq!(count.into_inner(), 10);
+    }
+
+    #[test]
+    fn test_smart_pointers_refcell() {
+        let bar = RefCell::new(Bar(String::from("hello")));
+        // Changing it through a & reference.
+        let bar_ref: &RefCell<Bar> = &bar;
+        bar_ref.borrow_mut().0.push_str(", world");
+        assert_eq!(bar.borrow().0, "hello, world");
+
+        // Many readers are fine.
+        let reader1 = bar.borrow();
+        let reader2 = bar.borrow();
+        assert_eq!(reader1.0, reader2.0);
+        // But a writer while there are readers breaks the rules.  The compiler
+        // can't see this, so RefCell catches it instead: borrow_mut() would
+        // panic, and try_borrow_mut() returns the error.
+        let err = bar.try_borrow_mut().unwrap_err();
+        println!("test_smart_pointers_refcell {err}");
+        drop(reader1);
+        assert!(bar.try_borrow_mut().is_err());
+        drop(reader2);
+        // Once the readers are gone, writing is fine again.
+        assert!(bar.try_borrow_mut().is_ok());
+    }
+
+    #[test]
+    fn test_smart_pointers_rc_refcell() {
+        // Rc<RefCell<_>> is the usual way to get shared *and* mutable.
+        let bar = Rc::new(RefCell::new(Bar(String::from("one"))));
+        let other_owner = Rc::clone(&bar);
+        other_owner.borrow_mut().0.push_str(", two");
+        assert_eq!(bar.borrow().0, "one, two");
+    }
+
+    #[test]
+    fn test_smart_pointers_cow() {
+        // Only allocate when something actually needs to change.
+        fn shout(s: &str) -> Cow<'_, str> {
+            if s.ends_with('!') {
+                Cow::Borrowed(s)
+            } else {
+                Cow::Owned(format!("{s}!"))
+            }
+        }
+        assert!(matches!(shout("hey!"), Cow::Borrowed("hey!")));
+        assert!(matches!(shout("hey"), Cow::Owned(_)));
+        // Cow derefs to the borrowed type either way.
+        assert_eq!(shout("hey").len(), 4);
+
+        // to_mut() clones the borrowed value, the first time only.
+        let bar = Bar(String::from("original"));
+        let mut cow: Cow<Bar> = Cow::Borrowed(&bar);
+        cow.to_mut().0.push_str(" (edited)");
+        assert_eq!(cow.0, "original (edited)");
+        assert_eq!(bar.0, "original");
+    }
+
+    #[test]
+    fn exercise_smart_pointers_list_len() {
+        let list = List::from_values(&[4, 5, 6, 7]);
+        unimplemented!(
+            r"
+            EXERCISE: Add a `len` method to List, like `sum`, and use it here.
+            Then try it with a loop instead of recursion.  What type does the
+            loop variable need to be?
+        "
+        );
+        // assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    fn exercise_smart_pointers_shared_bar() {
+        let bar = Bar(String::from("I like to eat crackers"));
+        unimplemented!(
+            r#"
+            EXERCISE: Put `bar` somewhere that two owners, `first` and `second`,
+            can both append to it.  `first` appends "..." and `second` appends
+            "at work!".  Then check the result through either owner.
+        "#
+        );
+        // assert_eq!(first.borrow().0, "I like to eat crackers...at work!");
+    }
+
+    #[test]
+    fn exercise_smart_pointers_cow() {
+        // Trailing spaces are a problem, but most Bars don't have any.
+        let clean = Bar(String::from("crackers"));
+        let messy = Bar(String::from("crackers   "));
+        unimplemented!(
+            r"
+            EXERCISE: Write `fn trim_bar(bar: &Bar) -> Cow<Bar>` that only
+            clones the Bar if it has to remove trailing spaces.
+        "
+        );
+        // assert!(matches!(trim_bar(&clean), Cow::Borrowed(_)));
+        // assert_eq!(trim_bar(&messy).into_owned(), clean);
+    }
+
+    #[test]
+    fn exercise_smart_pointers_foo_cell() {
+        let foo = Foo(1);
+        unimplemented!(
+            r"
+            EXERCISE: Write a closure that increments a Foo held in a Cell.
+            Cell::get needs Copy, which Foo isn't; which Cell methods still work?
+            (Cell::take needs Default, so you may want to add that to Foo.)
+        "
+        );
+    }
+}
@@ -0,0 +1,270 @@
+// Ownership is what makes threads safe in Rust.  The same rules from
+// ownership.rs apply -- one owner, many `&` or one `&mut` -- and two marker
+// traits carry them across threads:
+//
+//     Send: a value can be moved to another thread.
+//     Sync: a value can be shared (by `&`) between threads.
+//
+// The compiler works out both for you, and refuses to compile code that would
+// race.  The doc comments below show some programs it refuses.
+
+use std::thread;
+
+/// Run `f` on a new thread and wait for its answer.
+///
+/// Both `f` and what it returns have to be `Send`, since they cross between
+/// threads, and `'static`, since the new thread could outlive this function.
+/// `Rc` isn't `Send`: its reference count isn't atomic, so two threads
+/// cloning it at once could lose a count.
+///
+/// ```compile_fail
+/// use std::rc::Rc;
+/// use dlcm_rust_workshop::threads::run_on_thread;
+///
+/// let shared = Rc::new(5);
+/// let theirs = Rc::clone(&shared);
+/// run_on_thread(move || *theirs + 1);
+/// ```
+///
+/// `Arc` is the thread-safe `Rc`, so this one compiles:
+///
+/// ```
+/// use std::sync::Arc;
+/// use dlcm_rust_workshop::threads::run_on_thread;
+///
+/// let shared = Arc::new(5);
+/// let theirs = Arc::clone(&shared);
+/// assert_eq!(run_on_thread(move || *theirs + 1), 6);
+/// ```
+///
+/// `RefCell` is `Send` but not `Sync`: its borrow checks aren't atomic
+/// either, so it can't be shared, even inside an `Arc`.  Use a `Mutex`.
+///
+/// ```compile_fail
+/// use std::cell::RefCell;
+/// use std::sync::Arc;
+/// use dlcm_rust_workshop::threads::run_on_thread;
+///
+/// let shared = Arc::new(RefCell::new(5));
+/// let theirs = Arc::clone(&shared);
+/// run_on_thread(move || *theirs.borrow_mut() += 1);
+/// ```
+pub fn run_on_thread<T, F>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    // `join` only fails if the thread panicked; pass the panic on.
+    thread::spawn(f)
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// Add up `numbers` using `threads` threads.
+///
+/// `thread::spawn` can't borrow `numbers`, because the new thread might
+/// outlive it:
+///
+/// ```compile_fail
+/// let numbers = vec![1, 2, 3];
+/// let handle = std::thread::spawn(|| numbers.iter().sum::<i64>());
+/// ```
+///
+/// `thread::scope` promises to join every thread before it returns, so its
+/// threads can borrow whatever outlives the scope.  That's how this works.
+pub fn parallel_sum(numbers: &[i64], threads: usize) -> i64 {
+    let chunk_len = numbers.len().div_ceil(threads.max(1)).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = numbers
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(move || chunk.iter().sum::<i64>()))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ownership::Foo;
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex, RwLock};
+
+    #[test]
+    fn test_threads_spawn_move() {
+        let foo = Foo(1);
+        // `move` makes the closure take ownership of `foo`.  Without it, the
+        // closure would borrow `foo`, which isn't allowed (see `parallel_sum`).
+        let handle = thread::spawn(move || {
+            println!("test_threads_spawn_move {foo:?}");
+            foo.0 + 1
+        });
+        // foo has moved to the other thread; we can't use it anymore.
+        // println!("{foo:?}");
+        // `join` waits for the thread, and gives back what it returned.
+        assert_eq!(handle.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_threads_arc_mutex() {
+        // To share something that changes: Arc to share it, Mutex to change it.
+        let counter = Arc::new(Mutex::new(Foo(0)));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                // Each thread gets its own Arc, pointing to the same Mutex.
+                let counter = Arc::clone(&counter);
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        // The lock is released when `guard` is dropped.
+                        let mut guard = counter.lock().unwrap();
+                        guard.0 += 1;
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(counter.lock().unwrap().0, 800);
+        // Only our Arc is left.
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_threads_mutex_poisoning() {
+        let shared = Arc::new(Mutex::new(Foo(0)));
+        let theirs = Arc::clone(&shared);
+        let result = thread::spawn(move || {
+            let _guard = theirs.lock().unwrap();
+            panic!("oops, while holding the lock");
+        })
+        .join();
+        assert!(result.is_err());
+        // The data might be half-changed, so the Mutex is "poisoned"...
+        assert!(shared.lock().is_err());
+        // ...but you can still get at it if you know it's fine.
+        let foo = shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        assert_eq!(foo.0, 0);
+    }
+
+    #[test]
+    fn test_threads_rwlock() {
+        // RwLock allows many readers at once, or one writer: the same rule as
+        // & and &mut, but checked while the program runs.
+        let config = RwLock::new(String::from("v1"));
+        {
+            let a = config.read().unwrap();
+            let b = config.read().unwrap();
+            assert_eq!(*a, *b);
+            // Can't write while there are readers.
+            assert!(config.try_write().is_err());
+        }
+        config.write().unwrap().push_str("-patched");
+        assert_eq!(*config.read().unwrap(), "v1-patched");
+    }
+
+    #[test]
+    fn test_threads_channels() {
+        // Channels move values between threads, so nothing is shared at all.
+        // mpsc: multiple producers, single consumer.
+        let (sender, receiver) = mpsc::channel();
+        for id in 0..3 {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                for i in 0..3 {
+                    sender.send(Foo(id * 10 + i)).unwrap();
+                }
+            });
+        }
+        // The receiver stops once every sender is gone, including this one.
+        drop(sender);
+        let mut received: Vec<i32> = receiver.iter().map(|foo| foo.0).collect();
+        // The threads ran in any order, so sort before comparing.
+        received.sort();
+        assert_eq!(received, [0, 1, 2, 10, 11, 12, 20, 21, 22]);
+    }
+
+    #[test]
+    fn test_threads_scope() {
+        let mut foos = [Foo(1), Foo(2), Foo(3)];
+        let total = Foo(100);
+        thread::scope(|scope| {
+            // Scoped threads can borrow, even mutably, as long as the usual
+            // rules hold: each thread gets a different &mut Foo.
+            for foo in foos.iter_mut() {
+                scope.spawn(|| foo.0 += total.0);
+            }
+        });
+        // Every thread is done by here, so `foos` is ours again.
+        let values: Vec<i32> = foos.iter().map(|foo| foo.0).collect();
+        assert_eq!(values, [101, 102, 103]);
+        assert_eq!(parallel_sum(&[1, 2, 3, 4, 5, 6, 7], 3), 28);
+        assert_eq!(parallel_sum(&[], 3), 0);
+    }
+
+    #[test]
+    fn test_threads_send_sync() {
+        // A function with no body is a neat way to check a type's traits.
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+
+        is_send::<Foo>();
+        is_sync::<Foo>();
+        is_send::<Arc<Mutex<Foo>>>();
+        is_sync::<Mutex<Foo>>();
+        // Mutex makes a Send type Sync; RefCell doesn't.
+        is_send::<std::cell::RefCell<Foo>>();
+        // is_sync::<std::cell::RefCell<Foo>>();
+        // is_send::<std::rc::Rc<Foo>>();
+        assert_eq!(run_on_thread(|| Foo(7).0), 7);
+    }
+
+    #[test]
+    fn exercise_threads_move() {
+        let bar = String::from("I like to eat crackers");
+        unimplemented!(
+            r"
+            EXERCISE: Spawn a thread that appends '... on a thread!' to `bar`
+            and gives it back through `join`.  Why does the closure need `move`?
+        "
+        );
+        // let handle = thread::spawn(...);
+        // let bar = handle.join().unwrap();
+        // assert_eq!(bar, "I like to eat crackers... on a thread!");
+    }
+
+    #[test]
+    fn exercise_threads_shared_list() {
+        let list = Arc::new(Mutex::new(Vec::<i32>::new()));
+        unimplemented!(
+            r"
+            EXERCISE: Spawn 4 threads, where thread `i` pushes `i` onto `list`.
+            Join them all, then check the list has all four numbers.
+            What happens if you try it without the Arc?  Without the Mutex?
+        "
+        );
+        // let mut numbers = list.lock().unwrap().clone();
+        // numbers.sort();
+        // assert_eq!(numbers, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn exercise_threads_scope() {
+        let words = ["scoped", "threads", "can", "borrow"];
+        let lengths: Vec<usize> = Vec::new();
+        unimplemented!(
+            r"
+            EXERCISE: Use thread::scope to find the length of each word on its
+            own thread, without cloning or moving `words`.  Collect the lengths
+            in order, by joining the handles in order.
+        "
+        );
+        assert_eq!(lengths, [6, 7, 3, 6]);
+        assert_eq!(words.len(), 4);
+    }
+}